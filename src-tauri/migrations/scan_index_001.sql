@@ -0,0 +1,14 @@
+-- Scan index migration 001: persistent storage-analyzer scan results
+--
+-- One row per cached FileNode, keyed by normalized path. mtime/inode are the
+-- directory's values at scan time. Rows loaded on startup are never served
+-- as-is: they are the baseline of an incremental rescan, which only
+-- re-descends into directories whose mtime/inode no longer match.
+
+CREATE TABLE IF NOT EXISTS scan_index_entries (
+    path        TEXT PRIMARY KEY,
+    mtime       INTEGER NOT NULL,
+    inode       INTEGER,
+    node_json   TEXT NOT NULL,
+    scanned_at  INTEGER NOT NULL
+);
@@ -0,0 +1,6 @@
+-- Scan index migration 002: remember the ScanOptions each entry was built with
+--
+-- A cached tree is only reused for a request with identical options, so the
+-- options have to survive a restart along with the node.
+
+ALTER TABLE scan_index_entries ADD COLUMN options_json TEXT NOT NULL DEFAULT '{}';
@@ -0,0 +1,7 @@
+-- Scan index migration 003: drop entries sized before hard-link/allocated-size support
+--
+-- Older nodes have no apparent/allocated sizes and double-count hard links.
+-- They would deserialize with zeros and poison incremental reuse, so start
+-- from a clean index instead.
+
+DELETE FROM scan_index_entries;
@@ -0,0 +1,6 @@
+-- Scan index migration 004: drop entries written before nodes carried a kind
+--
+-- FileNode::kind defaults to "file" when missing, which would turn every
+-- cached directory into a file. Rescanning is cheaper than patching JSON.
+
+DELETE FROM scan_index_entries;
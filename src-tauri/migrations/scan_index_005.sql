@@ -0,0 +1,19 @@
+-- Scan index migration 005: named scan snapshots
+--
+-- A snapshot is a frozen copy of a cached scan tree, kept until deleted so
+-- two points in time can be diffed. Unlike scan_index_entries these rows are
+-- never revalidated or dropped by later migrations.
+
+CREATE TABLE IF NOT EXISTS scan_snapshots (
+    id           INTEGER PRIMARY KEY AUTOINCREMENT,
+    name         TEXT NOT NULL,
+    root         TEXT NOT NULL,
+    created_at   INTEGER NOT NULL,
+    size         INTEGER NOT NULL,
+    file_count   INTEGER NOT NULL,
+    options_json TEXT NOT NULL,
+    tree_json    TEXT NOT NULL,
+    UNIQUE(root, name)
+);
+
+CREATE INDEX IF NOT EXISTS idx_scan_snapshots_root ON scan_snapshots(root, created_at);
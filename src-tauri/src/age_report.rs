@@ -0,0 +1,340 @@
+// Age profile of a directory tree, for archiving decisions.
+//
+// One walk buckets bytes by how long ago files were last modified
+// (or accessed) and collects the largest subtrees in which nothing has been
+// touched for longer than a threshold. Only the outermost directory of a
+// stale branch is reported, so a cold 200 GB project doesn't also fill the
+// list with each of its subfolders. Access times are only as good as the
+// mount allows: `noatime` never updates them and the default `relatime` at
+// most once a day.
+//
+// The walk honours the scan's exclusions, filesystem boundaries and symlink
+// policy, and counts a hard-linked file once, so its totals line up with the
+// scanned tree.
+
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::SystemTime;
+
+use crate::scan_filter::ScanFilter;
+use crate::scanner::{HardLinks, ScanOptions, ScanStats, SymlinkPolicy};
+
+const DEFAULT_BUCKET_DAYS: [u64; 3] = [30, 180, 365];
+const DEFAULT_STALE_DAYS: u64 = 365;
+const DEFAULT_LIMIT: usize = 50;
+const MAX_LIMIT: usize = 10_000;
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum AgeBasis {
+    #[default]
+    Modified,
+    Accessed,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct AgeOptions {
+    pub basis: AgeBasis,
+    /// Bucket boundaries in days; defaults to 30, 180 and 365.
+    pub bucket_days: Option<Vec<u64>>,
+    /// A subtree is stale when its newest file is at least this many days
+    /// old. Defaults to 365.
+    pub stale_days: Option<u64>,
+    /// How many stale subtrees to return; defaults to 50.
+    pub limit: Option<usize>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct AgeBucket {
+    /// e.g. "<30d", "30d-180d", "180d-1y", ">1y".
+    pub label: String,
+    pub min_days: u64,
+    /// None for the open-ended oldest bucket.
+    pub max_days: Option<u64>,
+    pub bytes: u64,
+    pub files: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct StaleSubtree {
+    pub path: String,
+    pub size: u64,
+    pub file_count: u64,
+    /// Time of the most recently modified/accessed file inside, in seconds
+    /// since the Unix epoch.
+    pub newest: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct AgeReport {
+    pub root: String,
+    pub basis: AgeBasis,
+    /// Youngest first.
+    pub buckets: Vec<AgeBucket>,
+    pub stale_days: u64,
+    /// Largest first; the root itself is not included.
+    pub stale: Vec<StaleSubtree>,
+    /// Bytes in all stale subtrees, including those beyond `limit`.
+    pub stale_bytes: u64,
+    pub files_scanned: u64,
+    pub total_bytes: u64,
+}
+
+// A directory whose contents are still being walked.
+#[derive(Default)]
+struct OpenDir {
+    size: u64,
+    count: u64,
+    newest: u64,
+    /// Outermost stale subdirectories found so far.
+    stale: Vec<StaleSubtree>,
+}
+
+// Stale subtrees collected from directories that turned out not to be stale.
+struct StaleList {
+    limit: usize,
+    entries: Vec<StaleSubtree>,
+    total_bytes: u64,
+}
+
+impl StaleList {
+    fn extend(&mut self, subtrees: Vec<StaleSubtree>) {
+        self.total_bytes += subtrees.iter().map(|s| s.size).sum::<u64>();
+        self.entries.extend(subtrees);
+        if self.entries.len() > self.limit * 2 {
+            self.truncate();
+        }
+    }
+
+    fn truncate(&mut self) {
+        self.entries.sort_by_key(|s| std::cmp::Reverse(s.size));
+        self.entries.truncate(self.limit);
+    }
+}
+
+pub fn age_report(
+    root: &str,
+    options: &AgeOptions,
+    scan: &ScanOptions,
+    stats: Option<Arc<ScanStats>>,
+    cancel: Option<Arc<AtomicBool>>,
+) -> Result<AgeReport, String> {
+    let root_path = Path::new(root);
+    if !root_path.is_dir() {
+        return Err("Directory does not exist".to_string());
+    }
+    let mut boundaries = options.bucket_days.clone().unwrap_or_else(|| DEFAULT_BUCKET_DAYS.to_vec());
+    boundaries.sort_unstable();
+    boundaries.dedup();
+    boundaries.retain(|d| *d > 0);
+    let stale_days = options.stale_days.unwrap_or(DEFAULT_STALE_DAYS);
+    let now = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    let age_days = |secs: u64| now.saturating_sub(secs) / 86_400;
+
+    let mut buckets = make_buckets(&boundaries);
+    let mut stale = StaleList {
+        limit: options.limit.unwrap_or(DEFAULT_LIMIT).clamp(1, MAX_LIMIT),
+        entries: Vec::new(),
+        total_bytes: 0,
+    };
+    let mut files_scanned = 0u64;
+    let mut total_bytes = 0u64;
+    let filter = ScanFilter::new(root_path, scan)?;
+    let links = HardLinks::default();
+
+    // Pre-order, so excluded directories are pruned before they're entered
+    // (walkdir can't skip a directory it yields after its contents). `open`
+    // holds the directories still being walked, outermost first: an entry at
+    // depth d finishes every open directory at depth >= d.
+    let mut open: Vec<(std::path::PathBuf, OpenDir)> = vec![(root_path.to_path_buf(), OpenDir::default())];
+    let mut close = |open: &mut Vec<(std::path::PathBuf, OpenDir)>| {
+        let Some((path, dir)) = open.pop() else {
+            return;
+        };
+        let Some((_, parent)) = open.last_mut() else {
+            stale.extend(dir.stale);
+            return;
+        };
+        parent.size += dir.size;
+        parent.count += dir.count;
+        parent.newest = parent.newest.max(dir.newest);
+        if dir.count == 0 {
+            return;
+        }
+        if age_days(dir.newest) >= stale_days {
+            // Its stale subdirectories are covered by this one.
+            parent.stale.push(StaleSubtree {
+                path: path.to_string_lossy().to_string(),
+                size: dir.size,
+                file_count: dir.count,
+                newest: dir.newest,
+            });
+        } else {
+            stale.extend(dir.stale);
+        }
+    };
+
+    let walker = walkdir::WalkDir::new(root_path)
+        .min_depth(1)
+        .follow_links(scan.symlinks == SymlinkPolicy::Follow)
+        .into_iter()
+        .filter_entry(|e| e.metadata().map(|m| filter.allows(e.path(), &m)).unwrap_or(true));
+    for (idx, entry) in walker.enumerate() {
+        if idx % 100 == 0 && cancel.as_ref().map(|c| c.load(Ordering::Relaxed)).unwrap_or(false) {
+            return Err("Cancelled".to_string());
+        }
+        let entry = match entry {
+            Ok(e) => e,
+            Err(_) => {
+                if let Some(st) = &stats {
+                    st.errors.fetch_add(1, Ordering::Relaxed);
+                }
+                continue;
+            }
+        };
+        while open.len() > entry.depth() {
+            close(&mut open);
+        }
+
+        if entry.file_type().is_dir() {
+            open.push((entry.into_path(), OpenDir::default()));
+            continue;
+        }
+        if !entry.file_type().is_file() {
+            continue;
+        }
+        let meta = match entry.metadata() {
+            Ok(m) if links.first_sighting(&m) => m,
+            _ => continue,
+        };
+        files_scanned += 1;
+        total_bytes += meta.len();
+        if let Some(st) = &stats {
+            st.scanned_files.fetch_add(1, Ordering::Relaxed);
+            st.total_size.fetch_add(meta.len(), Ordering::Relaxed);
+            if files_scanned % 64 == 1 {
+                if let Some(parent) = entry.path().parent() {
+                    st.set_current_dir(parent);
+                }
+            }
+        }
+
+        let time = file_time(&meta, options.basis);
+        let bucket = boundaries.iter().position(|b| age_days(time) < *b).unwrap_or(boundaries.len());
+        buckets[bucket].bytes += meta.len();
+        buckets[bucket].files += 1;
+
+        if let Some((_, parent)) = open.last_mut() {
+            parent.size += meta.len();
+            parent.count += 1;
+            parent.newest = parent.newest.max(time);
+        }
+    }
+    while !open.is_empty() {
+        close(&mut open);
+    }
+    stale.truncate();
+
+    Ok(AgeReport {
+        root: root.to_string(),
+        basis: options.basis,
+        buckets,
+        stale_days,
+        stale: stale.entries,
+        stale_bytes: stale.total_bytes,
+        files_scanned,
+        total_bytes,
+    })
+}
+
+fn file_time(meta: &std::fs::Metadata, basis: AgeBasis) -> u64 {
+    let time = match basis {
+        AgeBasis::Modified => meta.modified(),
+        AgeBasis::Accessed => meta.accessed(),
+    };
+    time.ok()
+        .and_then(|t| t.duration_since(SystemTime::UNIX_EPOCH).ok())
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+// One bucket below each boundary plus an open-ended oldest one.
+fn make_buckets(boundaries: &[u64]) -> Vec<AgeBucket> {
+    let mut buckets = Vec::with_capacity(boundaries.len() + 1);
+    let mut min = 0;
+    for &max in boundaries {
+        let label = if min == 0 {
+            format!("<{}", days_label(max))
+        } else {
+            format!("{}-{}", days_label(min), days_label(max))
+        };
+        buckets.push(AgeBucket { label, min_days: min, max_days: Some(max), bytes: 0, files: 0 });
+        min = max;
+    }
+    let label = if min == 0 { "all".to_string() } else { format!(">{}", days_label(min)) };
+    buckets.push(AgeBucket { label, min_days: min, max_days: None, bytes: 0, files: 0 });
+    buckets
+}
+
+fn days_label(days: u64) -> String {
+    if days % 365 == 0 {
+        format!("{}y", days / 365)
+    } else {
+        format!("{}d", days)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TempDir;
+    use std::time::Duration;
+
+    fn write_aged(path: &Path, len: usize, days: u64) {
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, vec![0u8; len]).unwrap();
+        let mtime = SystemTime::now() - Duration::from_secs(days * 86_400);
+        std::fs::File::options().write(true).open(path).unwrap().set_modified(mtime).unwrap();
+    }
+
+    #[test]
+    fn buckets_bytes_and_reports_outermost_stale_subtrees() {
+        let root = TempDir::new("age");
+        write_aged(&root.join("old/a/x"), 100, 400);
+        write_aged(&root.join("old/b/y"), 200, 500);
+        write_aged(&root.join("mixed/new"), 10, 1);
+        write_aged(&root.join("mixed/cold/z"), 50, 200);
+
+        let scan = ScanOptions::default();
+        let report = age_report(root.to_str().unwrap(), &AgeOptions::default(), &scan, None, None).unwrap();
+        let labels: Vec<&str> = report.buckets.iter().map(|b| b.label.as_str()).collect();
+        assert_eq!(labels, vec!["<30d", "30d-180d", "180d-1y", ">1y"]);
+        let bytes: Vec<u64> = report.buckets.iter().map(|b| b.bytes).collect();
+        assert_eq!(bytes, vec![10, 0, 50, 300]);
+        assert_eq!(report.stale.len(), 1);
+        assert!(report.stale[0].path.ends_with("old"));
+        assert_eq!(report.stale[0].size, 300);
+
+        let options = AgeOptions { stale_days: Some(100), ..Default::default() };
+        let report = age_report(root.to_str().unwrap(), &options, &scan, None, None).unwrap();
+        let sizes: Vec<u64> = report.stale.iter().map(|s| s.size).collect();
+        assert_eq!(sizes, vec![300, 50]);
+        assert_eq!(report.stale_bytes, 350);
+
+        // Exclusions apply and a second hard link adds nothing.
+        #[cfg(unix)]
+        {
+            std::fs::hard_link(root.join("old/a/x"), root.join("mixed/x-link")).unwrap();
+            let scan = ScanOptions { exclude: vec!["cold".to_string()], ..Default::default() };
+            let report = age_report(root.to_str().unwrap(), &AgeOptions::default(), &scan, None, None).unwrap();
+            assert_eq!(report.total_bytes, 310);
+            assert_eq!(report.files_scanned, 3);
+        }
+    }
+}
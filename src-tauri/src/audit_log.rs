@@ -139,3 +139,47 @@ pub fn log_browser_action_event(event: BrowserActionEvent) -> Result<(), String>
     writeln!(file, "{}", line).map_err(|e| format!("Failed to write audit log: {}", e))?;
     Ok(())
 }
+
+// File operation performed by the app itself (Storage Analyzer deletes,
+// trash restores, moves and archives), as opposed to agent-proposed actions above.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct FileOperationEvent {
+    /// "trash" | "restore" | "delete" (permanent) | "move" | "copy" | "archive".
+    pub operation: String,
+    pub path: String,
+    /// Location inside the trash, for "trash" and "restore".
+    pub trashed_path: Option<String>,
+    /// New location for "move" and "copy", the archive for "archive".
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub destination: Option<String>,
+    /// Whether the original was removed, for "move" and "archive".
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub removed: Option<bool>,
+    /// "ok" | "error".
+    pub outcome: String,
+    pub error: Option<String>,
+}
+
+pub fn log_file_operation(event: &FileOperationEvent) -> Result<(), String> {
+    let path = audit_path()?;
+    rotate_if_needed(&path)?;
+
+    let ts = Utc::now().to_rfc3339();
+    let mut record = serde_json::to_value(event)
+        .map_err(|e| format!("Failed to serialize file audit event: {}", e))?;
+    if let Some(obj) = record.as_object_mut() {
+        obj.insert("kind".to_string(), serde_json::Value::String("file".to_string()));
+        obj.insert("ts".to_string(), serde_json::Value::String(ts));
+    }
+    let line = serde_json::to_string(&record)
+        .map_err(|e| format!("Failed to serialize file audit record: {}", e))?;
+
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .map_err(|e| format!("Failed to open audit log: {}", e))?;
+    writeln!(file, "{}", line).map_err(|e| format!("Failed to write audit log: {}", e))?;
+    Ok(())
+}
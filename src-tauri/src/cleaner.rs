@@ -3,6 +3,10 @@ use std::path::{Path, PathBuf};
 use std::fs;
 use std::time::SystemTime;
 
+use crate::cleaning_rules::{self, CleaningRule};
+use crate::dev_caches;
+use crate::log_cleanup::{self, LogKind};
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct JunkItem {
     pub path: String,
@@ -10,6 +14,17 @@ pub struct JunkItem {
     pub size: u64,
     pub description: String,
     pub age_days: Option<u32>, // Age in days since last modified
+    /// Project directory that build output belongs to (developer caches).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub project: Option<String>,
+    /// Newest modification inside, i.e. the last build or install, in
+    /// seconds since the Unix epoch (developer caches).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_build: Option<u64>,
+    /// How a log is cleaned: rotated ones are deleted, active ones
+    /// truncated in place (system logs).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub log_kind: Option<LogKind>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -27,6 +42,18 @@ pub struct CleaningOptions {
     pub min_age_days: Option<u32>, // Only delete files older than this
     pub dry_run: bool, // If true, don't actually delete, just return what would be deleted
     pub skip_errors: bool, // If true, continue on errors instead of stopping
+    /// Directories searched for per-project build output (`target/`,
+    /// `node_modules`) in the developer cache category.
+    #[serde(default)]
+    pub project_roots: Vec<String>,
+    /// journald vacuum limits: drop archived journal files older than this
+    /// many days, and the oldest ones until the journal fits in this many
+    /// bytes. Without either, files older than `min_age_days` (or 30 days)
+    /// go.
+    #[serde(default)]
+    pub journal_max_age_days: Option<u32>,
+    #[serde(default)]
+    pub journal_max_size: Option<u64>,
 }
 
 impl Default for CleaningOptions {
@@ -35,6 +62,9 @@ impl Default for CleaningOptions {
             min_age_days: None,
             dry_run: false,
             skip_errors: true,
+            project_roots: Vec::new(),
+            journal_max_age_days: None,
+            journal_max_size: None,
         }
     }
 }
@@ -46,6 +76,10 @@ pub struct DeletionResult {
     pub failed_count: usize,
     pub errors: Vec<String>,
     pub skipped_count: usize, // Files skipped due to age filter
+    /// Active logs among `deleted_count` that were (or in a dry run would be)
+    /// truncated, not removed.
+    #[serde(default)]
+    pub truncated_count: usize,
 }
 
 #[derive(Debug, Clone)]
@@ -57,6 +91,38 @@ struct CleaningPath {
     supports_wildcards: bool,
 }
 
+impl From<CleaningPath> for CleaningRule {
+    fn from(path: CleaningPath) -> Self {
+        CleaningRule {
+            category: path.category_id.to_string(),
+            category_name: Some(path.category_name.to_string()),
+            // Templates are globs; literal ones must not be read as patterns.
+            path: if path.supports_wildcards {
+                path.path_template.to_string()
+            } else {
+                glob::Pattern::escape(path.path_template)
+            },
+            description: path.description.to_string(),
+            patterns: Vec::new(),
+            exclude: Vec::new(),
+            min_age_days: None,
+            size_cap: None,
+        }
+    }
+}
+
+/// The built-in cleaning paths for this OS merged with the user's rules from
+/// `~/.ittoolkit/cleaning_rules.yaml`. A broken rules file is logged and
+/// ignored so the built-ins keep working.
+pub fn get_cleaning_rules() -> Vec<CleaningRule> {
+    let builtin = get_cleaning_paths().into_iter().map(CleaningRule::from).collect();
+    let user = cleaning_rules::load_user_rules().unwrap_or_else(|e| {
+        log::warn!("Ignoring cleaning rules: {}", e);
+        Vec::new()
+    });
+    cleaning_rules::merge_rules(builtin, user)
+}
+
 // macOS cleaning paths
 #[cfg(target_os = "macos")]
 fn get_cleaning_paths() -> Vec<CleaningPath> {
@@ -92,13 +158,7 @@ fn get_cleaning_paths() -> Vec<CleaningPath> {
             description: "System application logs",
             supports_wildcards: false,
         },
-        CleaningPath {
-            category_id: "system_logs",
-            category_name: "System Logs",
-            path_template: "/private/var/log",
-            description: "System logs",
-            supports_wildcards: false,
-        },
+        // /private/var/log is listed by log_cleanup.rs.
         
         // Temporary Files
         CleaningPath {
@@ -134,9 +194,9 @@ fn get_cleaning_paths() -> Vec<CleaningPath> {
         CleaningPath {
             category_id: "browser_cache",
             category_name: "Browser Caches",
-            path_template: "~/Library/Caches/Firefox/Profiles",
+            path_template: "~/Library/Caches/Firefox/Profiles/*/cache2",
             description: "Firefox browser cache",
-            supports_wildcards: false,
+            supports_wildcards: true,
         },
         CleaningPath {
             category_id: "browser_cache",
@@ -175,20 +235,6 @@ fn get_cleaning_paths() -> Vec<CleaningPath> {
             description: "iOS Simulator caches",
             supports_wildcards: false,
         },
-        CleaningPath {
-            category_id: "developer_cache",
-            category_name: "Developer Caches",
-            path_template: "~/.gradle/caches",
-            description: "Gradle build cache",
-            supports_wildcards: false,
-        },
-        CleaningPath {
-            category_id: "developer_cache",
-            category_name: "Developer Caches",
-            path_template: "~/.npm",
-            description: "npm package cache",
-            supports_wildcards: false,
-        },
         CleaningPath {
             category_id: "developer_cache",
             category_name: "Developer Caches",
@@ -224,13 +270,6 @@ fn get_cleaning_paths() -> Vec<CleaningPath> {
             description: "VS Code extensions cache",
             supports_wildcards: false,
         },
-        CleaningPath {
-            category_id: "developer_cache",
-            category_name: "Developer Caches",
-            path_template: "~/.cargo/registry",
-            description: "Rust cargo cache",
-            supports_wildcards: false,
-        },
     ]
 }
 
@@ -325,9 +364,9 @@ fn get_cleaning_paths() -> Vec<CleaningPath> {
         CleaningPath {
             category_id: "browser_cache",
             category_name: "Browser Caches",
-            path_template: "%APPDATA%\\Mozilla\\Firefox\\Profiles",
+            path_template: "%LOCALAPPDATA%\\Mozilla\\Firefox\\Profiles\\*\\cache2",
             description: "Firefox browser cache",
-            supports_wildcards: false,
+            supports_wildcards: true,
         },
         CleaningPath {
             category_id: "browser_cache",
@@ -338,20 +377,6 @@ fn get_cleaning_paths() -> Vec<CleaningPath> {
         },
         
         // Developer Tools
-        CleaningPath {
-            category_id: "developer_cache",
-            category_name: "Developer Caches",
-            path_template: "%USERPROFILE%\\.gradle\\caches",
-            description: "Gradle build cache",
-            supports_wildcards: false,
-        },
-        CleaningPath {
-            category_id: "developer_cache",
-            category_name: "Developer Caches",
-            path_template: "%LOCALAPPDATA%\\npm-cache",
-            description: "npm package cache",
-            supports_wildcards: false,
-        },
         CleaningPath {
             category_id: "developer_cache",
             category_name: "Developer Caches",
@@ -380,13 +405,6 @@ fn get_cleaning_paths() -> Vec<CleaningPath> {
             description: "Android build cache",
             supports_wildcards: false,
         },
-        CleaningPath {
-            category_id: "developer_cache",
-            category_name: "Developer Caches",
-            path_template: "%USERPROFILE%\\.cargo\\registry",
-            description: "Rust cargo cache",
-            supports_wildcards: false,
-        },
     ]
 }
 
@@ -419,21 +437,8 @@ fn get_cleaning_paths() -> Vec<CleaningPath> {
             supports_wildcards: false,
         },
         
-        // System Logs
-        CleaningPath {
-            category_id: "system_logs",
-            category_name: "System Logs",
-            path_template: "/var/log",
-            description: "System logs",
-            supports_wildcards: false,
-        },
-        CleaningPath {
-            category_id: "system_logs",
-            category_name: "System Logs",
-            path_template: "~/.xsession-errors",
-            description: "X session errors",
-            supports_wildcards: false,
-        },
+        // System Logs: /var/log and ~/.xsession-errors are listed by
+        // log_cleanup.rs, which tells rotated logs from active ones.
         
         // Trash
         CleaningPath {
@@ -448,40 +453,27 @@ fn get_cleaning_paths() -> Vec<CleaningPath> {
         CleaningPath {
             category_id: "browser_cache",
             category_name: "Browser Caches",
-            path_template: "~/.cache/google-chrome/Default/Cache",
+            path_template: "~/.cache/google-chrome/*/Cache",
             description: "Chrome browser cache",
-            supports_wildcards: false,
+            supports_wildcards: true,
         },
         CleaningPath {
             category_id: "browser_cache",
             category_name: "Browser Caches",
-            path_template: "~/.cache/mozilla/firefox",
+            path_template: "~/.cache/mozilla/firefox/*/cache2",
             description: "Firefox browser cache",
-            supports_wildcards: false,
+            supports_wildcards: true,
         },
         CleaningPath {
             category_id: "browser_cache",
             category_name: "Browser Caches",
-            path_template: "~/.cache/chromium/Default/Cache",
+            path_template: "~/.cache/chromium/*/Cache",
             description: "Chromium browser cache",
-            supports_wildcards: false,
+            supports_wildcards: true,
         },
         
-        // Package Manager Caches
-        CleaningPath {
-            category_id: "package_cache",
-            category_name: "Package Manager Caches",
-            path_template: "/var/cache/apt/archives",
-            description: "APT package cache",
-            supports_wildcards: false,
-        },
-        CleaningPath {
-            category_id: "package_cache",
-            category_name: "Package Manager Caches",
-            path_template: "/var/cache/dnf",
-            description: "DNF package cache",
-            supports_wildcards: false,
-        },
+        // Package Manager Caches. apt and dnf caches are cleaned through the
+        // tools themselves, see tool_cleanup.rs.
         CleaningPath {
             category_id: "package_cache",
             category_name: "Package Manager Caches",
@@ -491,20 +483,6 @@ fn get_cleaning_paths() -> Vec<CleaningPath> {
         },
         
         // Developer Tools
-        CleaningPath {
-            category_id: "developer_cache",
-            category_name: "Developer Caches",
-            path_template: "~/.gradle/caches",
-            description: "Gradle build cache",
-            supports_wildcards: false,
-        },
-        CleaningPath {
-            category_id: "developer_cache",
-            category_name: "Developer Caches",
-            path_template: "~/.npm",
-            description: "npm package cache",
-            supports_wildcards: false,
-        },
         CleaningPath {
             category_id: "developer_cache",
             category_name: "Developer Caches",
@@ -512,27 +490,6 @@ fn get_cleaning_paths() -> Vec<CleaningPath> {
             description: "Yarn package cache",
             supports_wildcards: false,
         },
-        CleaningPath {
-            category_id: "developer_cache",
-            category_name: "Developer Caches",
-            path_template: "~/.cache/pip",
-            description: "Python pip cache",
-            supports_wildcards: false,
-        },
-        CleaningPath {
-            category_id: "developer_cache",
-            category_name: "Developer Caches",
-            path_template: "~/.cargo/registry",
-            description: "Rust cargo cache",
-            supports_wildcards: false,
-        },
-        CleaningPath {
-            category_id: "developer_cache",
-            category_name: "Developer Caches",
-            path_template: "~/.m2/repository",
-            description: "Maven repository cache",
-            supports_wildcards: false,
-        },
         CleaningPath {
             category_id: "developer_cache",
             category_name: "Developer Caches",
@@ -550,7 +507,10 @@ fn get_cleaning_paths() -> Vec<CleaningPath> {
     ]
 }
 
-fn expand_path(path: &str) -> Option<PathBuf> {
+// Expands `~` and environment variables in a template and resolves the
+// result as a glob, so `~/.cache/mozilla/firefox/*/cache2` yields one path
+// per profile. Substituted values are escaped and can't act as patterns.
+fn expand_paths(path: &str) -> Vec<PathBuf> {
     use std::env;
     
     let mut expanded = path.to_string();
@@ -559,9 +519,9 @@ fn expand_path(path: &str) -> Option<PathBuf> {
     if expanded.starts_with('~') {
         if let Some(home_dir) = dirs::home_dir() {
             if expanded == "~" {
-                return Some(home_dir);
+                return vec![home_dir];
             }
-            expanded = expanded.replacen("~", &home_dir.to_string_lossy(), 1);
+            expanded = expanded.replacen("~", &glob::Pattern::escape(&home_dir.to_string_lossy()), 1);
         }
     }
     
@@ -581,7 +541,7 @@ fn expand_path(path: &str) -> Option<PathBuf> {
         for (var_name, var_value) in env_vars {
             if let Some(value) = var_value {
                 let pattern = format!("%{}%", var_name);
-                expanded = expanded.replace(&pattern, &value);
+                expanded = expanded.replace(&pattern, &glob::Pattern::escape(&value));
             }
         }
     }
@@ -590,18 +550,19 @@ fn expand_path(path: &str) -> Option<PathBuf> {
     {
         // Unix-like environment variable expansion
         if let Some(tmpdir) = env::var("TMPDIR").ok() {
-            expanded = expanded.replace("$TMPDIR", &tmpdir);
+            expanded = expanded.replace("$TMPDIR", &glob::Pattern::escape(&tmpdir));
         }
         if let Some(user) = env::var("USER").ok() {
-            expanded = expanded.replace("$USER", &user);
+            expanded = expanded.replace("$USER", &glob::Pattern::escape(&user));
         }
     }
     
-    let path_buf = PathBuf::from(expanded);
-    if path_buf.exists() {
-        Some(path_buf)
-    } else {
-        None
+    match glob::glob(&expanded) {
+        Ok(paths) => paths.flatten().collect(),
+        Err(e) => {
+            log::warn!("Invalid cleaning path {}: {}", path, e);
+            Vec::new()
+        }
     }
 }
 
@@ -612,7 +573,7 @@ fn calculate_dir_size(path: &Path) -> u64 {
     }
 }
 
-fn get_file_age_days(metadata: &fs::Metadata) -> Option<u32> {
+pub fn get_file_age_days(metadata: &fs::Metadata) -> Option<u32> {
     metadata
         .modified()
         .ok()
@@ -629,75 +590,132 @@ pub fn scan_junk_items() -> Vec<JunkCategory> {
 }
 
 pub fn scan_junk_items_with_options(options: CleaningOptions) -> Vec<JunkCategory> {
+    let mut categories = scan_rules(get_cleaning_rules(), &options);
+    let developer = dev_caches::scan_developer_caches(&options);
+    add_to_category(&mut categories, dev_caches::CATEGORY_ID, dev_caches::CATEGORY_NAME, developer);
+    let logs = log_cleanup::scan_logs(&options);
+    add_to_category(&mut categories, log_cleanup::CATEGORY_ID, log_cleanup::CATEGORY_NAME, logs);
+    categories
+}
+
+fn scan_rules(rules: Vec<CleaningRule>, options: &CleaningOptions) -> Vec<JunkCategory> {
     let mut categories: Vec<JunkCategory> = Vec::new();
-    let cleaning_paths = get_cleaning_paths();
     
-    for cleaning_path in cleaning_paths {
-        if let Some(path) = expand_path(cleaning_path.path_template) {
-            if !path.exists() {
-                continue;
+    for rule in rules {
+        let items = scan_rule(&rule, options);
+        let name = rule.category_name.as_deref().unwrap_or(&rule.category);
+        add_to_category(&mut categories, &rule.category, name, items);
+    }
+    
+    categories
+}
+
+fn add_to_category(categories: &mut Vec<JunkCategory>, id: &str, name: &str, items: Vec<JunkItem>) {
+    if items.is_empty() {
+        return;
+    }
+    let total_size: u64 = items.iter().map(|i| i.size).sum();
+
+    // Check if category already exists
+    if let Some(cat) = categories.iter_mut().find(|c| c.id == id) {
+        cat.items.extend(items);
+        cat.total_size += total_size;
+    } else {
+        categories.push(JunkCategory {
+            id: id.to_string(),
+            name: name.to_string(),
+            description: format!("Files in {}", name),
+            items,
+            total_size,
+            icon: id.to_string(),
+        });
+    }
+}
+
+// Entries of one rule's directories that count as junk. A template that
+// resolves to a file is listed itself.
+fn scan_rule(rule: &CleaningRule, options: &CleaningOptions) -> Vec<JunkItem> {
+    let include: Vec<glob::Pattern> = rule.patterns.iter().filter_map(|p| glob::Pattern::new(p).ok()).collect();
+    let exclude: Vec<glob::Pattern> = rule.exclude.iter().filter_map(|p| glob::Pattern::new(p).ok()).collect();
+    let wanted = |name: &str| {
+        (include.is_empty() || include.iter().any(|p| p.matches(name))) && !exclude.iter().any(|p| p.matches(name))
+    };
+    // Age filters run before anything is measured; entries whose age is
+    // unknown are skipped.
+    let min_ages: Vec<u32> = [options.min_age_days, rule.min_age_days].into_iter().flatten().collect();
+    let old_enough = |age: Option<u32>| min_ages.iter().all(|&min| age.is_some_and(|age| age >= min));
+    
+    let mut items = Vec::new();
+    
+    for path in expand_paths(&rule.path) {
+        let meta = match fs::metadata(&path) {
+            Ok(meta) => meta,
+            Err(_) => continue,
+        };
+        if !meta.is_dir() {
+            let name = path.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+            let age_days = get_file_age_days(&meta);
+            if wanted(&name) && old_enough(age_days) {
+                items.push(JunkItem {
+                    path: path.to_string_lossy().to_string(),
+                    name,
+                    size: meta.len(),
+                    description: rule.description.clone(),
+                    age_days,
+                    project: None,
+                    last_build: None,
+                    log_kind: None,
+                });
             }
-            
-            let mut items = Vec::new();
-            let mut total_size = 0;
-            
-            // Scan directory contents
-            if let Ok(read_dir) = fs::read_dir(&path) {
-                for entry in read_dir.flatten() {
-                    if let Ok(meta) = entry.metadata() {
-                        // Calculate age
-                        let age_days = get_file_age_days(&meta);
-                        
-                        // Apply age filter if specified
-                        if let Some(min_age) = options.min_age_days {
-                            if let Some(age) = age_days {
-                                if age < min_age {
-                                    continue; // Skip files that are too new
-                                }
-                            } else {
-                                continue; // Skip if we can't determine age
-                            }
-                        }
-                        
-                        let size = if meta.is_dir() {
-                            calculate_dir_size(&entry.path())
-                        } else {
-                            meta.len()
-                        };
-                        
-                        total_size += size;
-                        
-                        items.push(JunkItem {
-                            path: entry.path().to_string_lossy().to_string(),
-                            name: entry.file_name().to_string_lossy().to_string(),
-                            size,
-                            description: cleaning_path.description.to_string(),
-                            age_days,
-                        });
-                    }
+            continue;
+        }
+
+        // Scan directory contents
+        if let Ok(read_dir) = fs::read_dir(&path) {
+            for entry in read_dir.flatten() {
+                let name = entry.file_name().to_string_lossy().to_string();
+                if !wanted(&name) {
+                    continue;
                 }
-            }
-            
-            if !items.is_empty() {
-                // Check if category already exists
-                if let Some(cat) = categories.iter_mut().find(|c| c.id == cleaning_path.category_id) {
-                    cat.items.extend(items);
-                    cat.total_size += total_size;
-                } else {
-                    categories.push(JunkCategory {
-                        id: cleaning_path.category_id.to_string(),
-                        name: cleaning_path.category_name.to_string(),
-                        description: format!("Files in {}", cleaning_path.category_name),
-                        items,
-                        total_size,
-                        icon: cleaning_path.category_id.to_string(),
+                if let Ok(meta) = entry.metadata() {
+                    let age_days = get_file_age_days(&meta);
+                    if !old_enough(age_days) {
+                        continue;
+                    }
+                    let size = if meta.is_dir() {
+                        calculate_dir_size(&entry.path())
+                    } else {
+                        meta.len()
+                    };
+                    
+                    items.push(JunkItem {
+                        path: entry.path().to_string_lossy().to_string(),
+                        name,
+                        size,
+                        description: rule.description.clone(),
+                        age_days,
+                        project: None,
+                        last_build: None,
+                        log_kind: None,
                     });
                 }
             }
         }
     }
+
+    if let Some(cap) = rule.size_cap {
+        // Keep the newest entries that fit under the cap; entries of unknown
+        // age count as oldest.
+        items.sort_by_key(|i| i.age_days.unwrap_or(u32::MAX));
+        let mut kept = 0u64;
+        let over = items.iter().position(|i| {
+            kept += i.size;
+            kept > cap
+        });
+        items.drain(..over.unwrap_or(items.len()));
+    }
     
-    categories
+    items
 }
 
 pub fn delete_junk_items(paths: Vec<String>) -> Result<(), String> {
@@ -718,6 +736,7 @@ pub fn delete_junk_items_with_options(
     let mut deleted_size = 0;
     let mut failed_count = 0;
     let mut skipped_count = 0;
+    let mut truncated_count = 0;
     let mut errors = Vec::new();
     
     for path in paths {
@@ -745,9 +764,14 @@ pub fn delete_junk_items_with_options(
             }
         };
         
-        // Apply age filter if specified
+        // Apply age filter if specified, measured the way the scan did
         if let Some(min_age) = options.min_age_days {
-            if let Some(age) = get_file_age_days(&metadata) {
+            let age = if metadata.is_dir() && dev_caches::is_developer_cache(p) {
+                dev_caches::age_days(p)
+            } else {
+                get_file_age_days(&metadata)
+            };
+            if let Some(age) = age {
                 if age < min_age {
                     skipped_count += 1;
                     continue; // Skip files that are too new
@@ -765,18 +789,27 @@ pub fn delete_junk_items_with_options(
             metadata.len()
         };
         
+        // Active logs are emptied instead of deleted, as daemons still hold
+        // them open.
+        let truncate = p.is_file() && log_cleanup::is_active_log(p);
+
         // Dry run mode - don't actually delete
         if options.dry_run {
             deleted_count += 1;
             deleted_size += size;
+            if truncate {
+                truncated_count += 1;
+            }
             continue;
         }
         
         // Perform actual deletion
-        let result = if p.is_file() {
+        let result = if truncate {
+            log_cleanup::truncate_log(p)
+        } else if p.is_file() {
             fs::remove_file(p)
         } else if p.is_dir() {
-            fs::remove_dir_all(p)
+            remove_dir_all_writable(p)
         } else {
             skipped_count += 1;
             continue;
@@ -786,6 +819,9 @@ pub fn delete_junk_items_with_options(
             Ok(_) => {
                 deleted_count += 1;
                 deleted_size += size;
+                if truncate {
+                    truncated_count += 1;
+                }
             }
             Err(e) => {
                 if !options.skip_errors {
@@ -803,5 +839,127 @@ pub fn delete_junk_items_with_options(
         failed_count,
         errors,
         skipped_count,
+        truncated_count,
     })
 }
+
+// Some caches are read-only on purpose (Go's module cache), which stops
+// remove_dir_all. Make the tree writable and retry once.
+fn remove_dir_all_writable(path: &Path) -> std::io::Result<()> {
+    match fs::remove_dir_all(path) {
+        Err(e) if e.kind() == std::io::ErrorKind::PermissionDenied => {
+            for entry in walkdir::WalkDir::new(path).into_iter().flatten() {
+                if !entry.path_is_symlink() {
+                    make_writable(entry.path());
+                }
+            }
+            fs::remove_dir_all(path)
+        }
+        result => result,
+    }
+}
+
+#[cfg(unix)]
+fn make_writable(path: &Path) {
+    use std::os::unix::fs::PermissionsExt;
+    if let Ok(meta) = fs::metadata(path) {
+        let mode = meta.permissions().mode();
+        if meta.is_dir() && mode & 0o700 != 0o700 {
+            let _ = fs::set_permissions(path, fs::Permissions::from_mode(mode | 0o700));
+        }
+    }
+}
+
+// Read-only files can't be deleted on Windows.
+#[cfg(not(unix))]
+#[allow(clippy::permissions_set_readonly_false)]
+fn make_writable(path: &Path) {
+    if let Ok(meta) = fs::metadata(path) {
+        let mut permissions = meta.permissions();
+        if permissions.readonly() {
+            permissions.set_readonly(false);
+            let _ = fs::set_permissions(path, permissions);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TempDir;
+    use std::time::Duration;
+
+    fn write_aged(path: &Path, len: usize, days: u64) {
+        fs::write(path, vec![0u8; len]).unwrap();
+        let mtime = SystemTime::now() - Duration::from_secs(days * 86_400);
+        fs::File::options().write(true).open(path).unwrap().set_modified(mtime).unwrap();
+    }
+
+    #[test]
+    fn rule_patterns_size_cap_and_age() {
+        let root = TempDir::new("cleaner");
+        write_aged(&root.join("job-1"), 100, 1);
+        write_aged(&root.join("job-2"), 100, 10);
+        write_aged(&root.join("job-3"), 100, 20);
+        write_aged(&root.join("keep.txt"), 100, 30);
+        write_aged(&root.join("job-4"), 100, 40);
+
+        let rule = CleaningRule {
+            category: "ci".to_string(),
+            category_name: None,
+            path: root.to_string_lossy().to_string(),
+            description: "CI".to_string(),
+            patterns: vec!["job-*".to_string()],
+            exclude: vec!["*-4".to_string()],
+            min_age_days: None,
+            size_cap: Some(150),
+        };
+        let names = |items: Vec<JunkItem>| items.into_iter().map(|i| i.name).collect::<Vec<_>>();
+        assert_eq!(names(scan_rule(&rule, &CleaningOptions::default())), vec!["job-2", "job-3"]);
+
+        // The cap only counts entries that pass the age filter: job-2 is now
+        // the newest one and fills it.
+        let rule = CleaningRule { min_age_days: Some(5), ..rule };
+        assert_eq!(names(scan_rule(&rule, &CleaningOptions::default())), vec!["job-3"]);
+        let options = CleaningOptions { min_age_days: Some(15), ..CleaningOptions::default() };
+        assert!(scan_rule(&rule, &options).is_empty());
+
+        let categories = scan_rules(vec![rule], &CleaningOptions::default());
+        assert_eq!(categories[0].name, "ci");
+        assert_eq!(categories[0].total_size, 100);
+
+        // Glob templates, and literal ones that only look like globs.
+        fs::create_dir_all(root.join("profiles/a.default/cache2")).unwrap();
+        fs::create_dir_all(root.join("profiles/b.default-release/cache2")).unwrap();
+        fs::create_dir_all(root.join("profiles/[x]")).unwrap();
+        write_aged(&root.join("profiles/a.default/cache2/entry"), 10, 1);
+        write_aged(&root.join("profiles/b.default-release/cache2/entry"), 10, 1);
+        write_aged(&root.join("profiles/[x]/entry"), 10, 1);
+        let template = |path: &str, supports_wildcards: bool| CleaningRule::from(CleaningPath {
+            category_id: "browser_cache",
+            category_name: "Browser Caches",
+            path_template: Box::leak(format!("{}/profiles/{}", root.display(), path).into_boxed_str()),
+            description: "cache",
+            supports_wildcards,
+        });
+        let items = scan_rule(&template("*.default*/cache2", true), &CleaningOptions::default());
+        assert_eq!(items.len(), 2);
+        assert_eq!(scan_rule(&template("[x]", false), &CleaningOptions::default()).len(), 1);
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let locked = root.join("gomod/pkg@v1");
+            fs::create_dir_all(&locked).unwrap();
+            fs::write(locked.join("go.mod"), b"module pkg").unwrap();
+            fs::set_permissions(&locked, fs::Permissions::from_mode(0o555)).unwrap();
+            let result = delete_junk_items_with_options(
+                vec![root.join("gomod").to_string_lossy().to_string()],
+                CleaningOptions::default(),
+            )
+            .unwrap();
+            assert_eq!(result.deleted_count, 1);
+            assert!(!root.join("gomod").exists());
+        }
+    }
+}
@@ -0,0 +1,192 @@
+// Site-specific cleaning rules from ~/.ittoolkit/cleaning_rules.yaml.
+//
+// Lets a team add junk locations (build caches, app logs, CI workspaces) to
+// the cleaner without recompiling. User rules are merged with the built-in
+// list from cleaner.rs: a rule for the same category and path replaces the
+// built-in one, anything else is added. A missing file means no extra rules.
+//
+//   rules:
+//     - category: ci_workspaces
+//       category_name: CI Workspaces
+//       path: /srv/ci/workspace
+//       description: Finished CI job workspaces
+//       patterns: ["job-*"]
+//       exclude: ["job-*-keep"]
+//       min_age_days: 14
+//       size_cap: 50G
+
+use serde::{Deserialize, Deserializer, Serialize};
+use std::fs;
+use std::path::PathBuf;
+
+const RULES_SUBDIR: &str = ".ittoolkit";
+const RULES_FILE: &str = "cleaning_rules.yaml";
+
+/// One location the cleaner lists entries from.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct CleaningRule {
+    /// Category id; rules sharing one are shown together.
+    pub category: String,
+    /// Display name. Defaults to the built-in category's name, or the id.
+    #[serde(default)]
+    pub category_name: Option<String>,
+    /// Directory whose entries are junk. `~`, `$TMPDIR`/`$USER` and
+    /// `%VAR%` (Windows) are expanded, and the result is a glob:
+    /// `~/.mozilla/firefox/*.default*/cache2` covers every profile. A path
+    /// that resolves to a file is itself the junk item.
+    pub path: String,
+    #[serde(default)]
+    pub description: String,
+    /// Glob patterns for entry names to include; empty matches everything.
+    #[serde(default, alias = "include")]
+    pub patterns: Vec<String>,
+    /// Glob patterns for entry names to leave alone, even if included.
+    #[serde(default)]
+    pub exclude: Vec<String>,
+    /// Only entries last modified at least this many days ago. Applies on
+    /// top of `CleaningOptions::min_age_days`.
+    #[serde(default)]
+    pub min_age_days: Option<u32>,
+    /// Bytes the location may keep: of the entries old enough to be listed,
+    /// the newest up to this size are left alone and only the older ones
+    /// beyond it are listed. Accepts plain byte counts or sizes such as
+    /// "500M" and "2GiB".
+    #[serde(default, deserialize_with = "deserialize_size")]
+    pub size_cap: Option<u64>,
+}
+
+#[derive(Debug, Deserialize, Default)]
+struct RulesFile {
+    #[serde(default)]
+    rules: Vec<CleaningRule>,
+}
+
+pub fn rules_path() -> Result<PathBuf, String> {
+    let home = dirs::home_dir().ok_or_else(|| "Could not resolve home directory".to_string())?;
+    Ok(home.join(RULES_SUBDIR).join(RULES_FILE))
+}
+
+/// User rules from the config file; empty if it doesn't exist.
+pub fn load_user_rules() -> Result<Vec<CleaningRule>, String> {
+    let path = rules_path()?;
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let content = fs::read_to_string(&path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    parse_rules(&content).map_err(|e| format!("{}: {}", path.display(), e))
+}
+
+fn parse_rules(content: &str) -> Result<Vec<CleaningRule>, String> {
+    if content.trim().is_empty() {
+        return Ok(Vec::new());
+    }
+    let file: RulesFile = serde_yaml::from_str(content).map_err(|e| format!("Cleaning rules parse error: {}", e))?;
+    for rule in &file.rules {
+        if rule.category.trim().is_empty() || rule.path.trim().is_empty() {
+            return Err("Every cleaning rule needs a category and a path".to_string());
+        }
+        glob::Pattern::new(&rule.path).map_err(|e| format!("Invalid path {:?}: {}", rule.path, e))?;
+        for pattern in rule.patterns.iter().chain(&rule.exclude) {
+            glob::Pattern::new(pattern).map_err(|e| format!("Invalid pattern {:?}: {}", pattern, e))?;
+        }
+    }
+    Ok(file.rules)
+}
+
+/// Built-in rules with the user's merged in. Rules without a category name
+/// take the one of the built-in category they join.
+pub fn merge_rules(builtin: Vec<CleaningRule>, user: Vec<CleaningRule>) -> Vec<CleaningRule> {
+    let mut merged = builtin;
+    for mut rule in user {
+        if rule.category_name.is_none() {
+            rule.category_name = merged
+                .iter()
+                .find(|r| r.category == rule.category)
+                .and_then(|r| r.category_name.clone());
+        }
+        match merged.iter_mut().find(|r| r.category == rule.category && r.path == rule.path) {
+            Some(existing) => *existing = rule,
+            None => merged.push(rule),
+        }
+    }
+    merged
+}
+
+fn deserialize_size<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<u64>, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Size {
+        Bytes(u64),
+        Text(String),
+    }
+    match Option::<Size>::deserialize(deserializer)? {
+        None => Ok(None),
+        Some(Size::Bytes(n)) => Ok(Some(n)),
+        Some(Size::Text(s)) => parse_size(&s).map(Some).map_err(serde::de::Error::custom),
+    }
+}
+
+// "1500", "500M", "2GB", "1.5GiB": binary multiples either way, as du does.
+fn parse_size(text: &str) -> Result<u64, String> {
+    let text = text.trim();
+    let split = text.find(|c: char| !(c.is_ascii_digit() || c == '.')).unwrap_or(text.len());
+    let (number, unit) = text.split_at(split);
+    let number: f64 = number.parse().map_err(|_| format!("Invalid size {:?}", text))?;
+    let shift = match unit.trim().to_ascii_uppercase().trim_end_matches("IB").trim_end_matches('B') {
+        "" => 0,
+        "K" => 10,
+        "M" => 20,
+        "G" => 30,
+        "T" => 40,
+        _ => return Err(format!("Invalid size unit in {:?}", text)),
+    };
+    Ok((number * (1u64 << shift) as f64) as u64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_and_merges_user_rules() {
+        let yaml = r#"
+rules:
+  - category: system_cache
+    path: ~/.cache
+    patterns: ["*.tmp"]
+    size_cap: 1.5G
+  - category: ci_workspaces
+    category_name: CI Workspaces
+    path: /srv/ci/*/workspace
+    include: ["job-*"]
+    exclude: ["*-keep"]
+    min_age_days: 14
+    size_cap: 4096
+"#;
+        let rules = parse_rules(yaml).unwrap();
+        assert_eq!(rules[0].size_cap, Some(1536 * 1024 * 1024));
+        assert_eq!(rules[1].size_cap, Some(4096));
+        assert_eq!(rules[1].min_age_days, Some(14));
+        assert_eq!((rules[1].patterns.len(), rules[1].exclude.len()), (1, 1));
+        assert!(parse_rules("rules:\n  - category: x\n    path: /x/[\n").is_err());
+        assert!(parse_rules("rules:\n  - category: x\n    path: /x\n    patterns: ['[']\n").is_err());
+        assert!(parse_rules("rules:\n  - category: x\n    path: /x\n    size_cap: 5Q\n").is_err());
+        assert!(parse_rules("").unwrap().is_empty());
+
+        let builtin = vec![CleaningRule {
+            category: "system_cache".to_string(),
+            category_name: Some("System Caches".to_string()),
+            path: "~/.cache".to_string(),
+            description: "User application caches".to_string(),
+            patterns: Vec::new(),
+            exclude: Vec::new(),
+            min_age_days: None,
+            size_cap: None,
+        }];
+        let merged = merge_rules(builtin, rules);
+        assert_eq!(merged.len(), 2);
+        assert_eq!(merged[0].patterns, vec!["*.tmp"]);
+        assert_eq!(merged[0].category_name.as_deref(), Some("System Caches"));
+        assert_eq!(merged[1].category, "ci_workspaces");
+    }
+}
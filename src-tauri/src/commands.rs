@@ -1,9 +1,27 @@
 use tauri::{command, AppHandle, Emitter};
-use crate::scanner::{scan_directory, FileNode, ScanStats};
+use crate::scanner::{
+    apply_node_update, expand_dir, find_node, measure_path, scan_directory_incremental, scan_directory_with_sizes,
+    update_point, ChangeKind, DirSizes, FileNode, IncrementalScan, NodeChange, NodeKind,
+    ScanOptions, ScanStats,
+};
+use crate::scan_index::{IndexedEntry, ScanIndex};
+use crate::duplicates::{self, DuplicateReport, DuplicateSelection};
+use crate::ai::{FileInfo, ScanSummary};
+use crate::largest::{self, LargestFilter, LargestReport};
+use crate::owners::{self, OwnerReport};
+use crate::age_report::{self, AgeOptions, AgeReport};
+use crate::snapshots::{self, SnapshotDiff, SnapshotInfo};
+use crate::scan_export::{self, ExportFormat, ExportResult};
 use crate::cleaner::{self, JunkCategory};
+use crate::cleaning_rules::CleaningRule;
+use crate::tool_cleanup::{self, CleanupTool, PruneResult, ToolCategory};
+use crate::audit_log::{self, FileOperationEvent};
+use crate::trash::{self, TrashedItem};
+use crate::file_ops::{self, ArchiveFormat, FileOpProgress, FileOpResult};
+use crate::mounts;
 use std::collections::HashMap;
-use std::sync::{Arc, Mutex, RwLock};
-use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::time::{SystemTime, Duration};
 use lazy_static::lazy_static;
 use std::path::Path;
@@ -11,22 +29,39 @@ use sysinfo::Disks;
 
 struct CacheEntry {
     node: FileNode,
+    options: ScanOptions,
     timestamp: SystemTime,
-}
-
-// Global state to manage cancellation
-struct ScanState {
-    cancel_token: Arc<AtomicBool>,
+    /// Deep directory totals from the scan, shared by every entry it cached.
+    /// Not persisted; entries loaded from the scan index start empty.
+    dir_sizes: Arc<DirSizes>,
+    /// Loaded from the scan index rather than scanned in this session. Never
+    /// served as-is: `scan_dir` revalidates it with an incremental rescan,
+    /// which only re-descends into directories that changed since.
+    needs_rescan: bool,
 }
 
 lazy_static! {
     static ref SCAN_CACHE: Mutex<HashMap<String, CacheEntry>> = Mutex::new(HashMap::new());
-    static ref SCAN_STATE: RwLock<ScanState> = RwLock::new(ScanState { 
-        cancel_token: Arc::new(AtomicBool::new(false)) 
-    });
+    // Cancellation tokens of the scans in flight, keyed by scan id.
+    static ref RUNNING_SCANS: Mutex<HashMap<String, Arc<AtomicBool>>> = Mutex::new(HashMap::new());
+    // Cancellation tokens of the moves and archives in flight, keyed by op id.
+    static ref RUNNING_FILE_OPS: Mutex<HashMap<String, Arc<AtomicBool>>> = Mutex::new(HashMap::new());
+    // On-disk mirror of SCAN_CACHE. None if the database couldn't be opened;
+    // scanning still works, results just don't survive a restart.
+    static ref SCAN_INDEX: Option<ScanIndex> = match ScanIndex::new() {
+        Ok(index) => Some(index),
+        Err(e) => {
+            log::error!("Failed to open scan index: {}", e);
+            None
+        }
+    };
 }
 
-const CACHE_TTL: u64 = 60 * 60; 
+// Entries scanned in this session are served for this long, then rescanned
+// in full: a directory's mtime only covers its direct entries, so file sizes
+// changing deeper down go unnoticed by mtime checks. Entries from the scan
+// index are revalidated by an incremental rescan instead.
+const CACHE_TTL: u64 = 60 * 60;
 
 fn normalize_path(path: &str) -> String {
     let mut s = path.to_string();
@@ -39,69 +74,292 @@ fn normalize_path(path: &str) -> String {
     s
 }
 
+/// Populate SCAN_CACHE from the persistent scan index. Called once at startup.
+pub fn load_scan_index() {
+    let index = match SCAN_INDEX.as_ref() {
+        Some(i) => i,
+        None => return,
+    };
+    let entries = match index.load_all() {
+        Ok(e) => e,
+        Err(e) => {
+            log::warn!("Failed to load scan index: {}", e);
+            return;
+        }
+    };
+    if let Ok(mut cache) = SCAN_CACHE.lock() {
+        let count = entries.len();
+        for entry in entries {
+            // A scan that finished while the index was loading is newer.
+            cache.entry(entry.key).or_insert(CacheEntry {
+                node: entry.node,
+                options: entry.options,
+                timestamp: SystemTime::UNIX_EPOCH + Duration::from_secs(entry.scanned_at),
+                dir_sizes: Arc::default(),
+                needs_rescan: true,
+            });
+        }
+        log::info!("Loaded {} scan index entries", count);
+    }
+}
+
+fn is_cache_valid(entry: &CacheEntry) -> bool {
+    !entry.needs_rescan && entry.timestamp.elapsed().is_ok_and(|elapsed| elapsed.as_secs() < CACHE_TTL)
+}
+
+fn persist_entries(entries: Vec<IndexedEntry>) {
+    if let Some(index) = SCAN_INDEX.as_ref() {
+        if let Err(e) = index.upsert_many(&entries) {
+            log::warn!("Failed to persist scan index: {}", e);
+        }
+    }
+}
+
 #[derive(Clone, serde::Serialize)]
 struct ScanProgress {
+    scan_id: String,
     path: String, // Just the root path being scanned
     count: u64,
     size: u64,
     errors: u64,
+    /// Directory a worker entered most recently.
+    current_path: String,
+    files_per_sec: f64,
+    bytes_per_sec: f64,
+    /// Total the scan is expected to reach, when it can be estimated.
+    expected_size: Option<u64>,
+    eta_secs: Option<u64>,
 }
 
+// Throughput is measured over the last few seconds of samples: steadier than
+// a single 100 ms tick, more current than the whole-scan average.
+const RATE_WINDOW: Duration = Duration::from_secs(3);
+
+/// Emitted when a scan starts walking, so callers that didn't pick a scan id
+/// learn the generated one before the scan finishes.
+#[derive(Clone, serde::Serialize)]
+struct ScanStarted {
+    scan_id: String,
+    path: String,
+}
+
+// Every scan-type command takes an optional caller-chosen `scan_id`; one is
+// generated when it's omitted. Progress events carry it, and `cancel_scan`
+// can target it, so several scans (e.g. two drives) can run side by side.
+
 #[command]
-pub async fn scan_dir(app: AppHandle, path: String) -> Result<FileNode, String> {
-    scan_dir_internal(app, path, false).await
+pub async fn scan_dir(
+    app: AppHandle,
+    path: String,
+    options: Option<ScanOptions>,
+    scan_id: Option<String>,
+) -> Result<FileNode, String> {
+    scan_dir_internal(app, path, options.unwrap_or_default(), false, scan_id).await
 }
 
 #[command]
-pub async fn refresh_scan(app: AppHandle, path: String) -> Result<FileNode, String> {
-    scan_dir_internal(app, path, true).await
+pub async fn refresh_scan(
+    app: AppHandle,
+    path: String,
+    options: Option<ScanOptions>,
+    scan_id: Option<String>,
+) -> Result<FileNode, String> {
+    scan_dir_internal(app, path, options.unwrap_or_default(), true, scan_id).await
 }
 
+/// Cancel the scan with `scan_id`. Callers that let the id be generated get
+/// it from the `scan-started` event.
 #[command]
-pub fn cancel_scan() {
-    if let Ok(state) = SCAN_STATE.read() {
-        state.cancel_token.store(true, Ordering::Relaxed);
+pub fn cancel_scan(scan_id: String) {
+    if let Ok(running) = RUNNING_SCANS.lock() {
+        if let Some(token) = running.get(&scan_id) {
+            token.store(true, Ordering::Relaxed);
+        }
     }
 }
 
-async fn scan_dir_internal(app: AppHandle, path: String, force_refresh: bool) -> Result<FileNode, String> {
+/// Ids of the scans currently in flight.
+#[command]
+pub fn list_running_scans() -> Vec<String> {
+    RUNNING_SCANS.lock().map(|r| r.keys().cloned().collect()).unwrap_or_default()
+}
+
+#[command]
+pub async fn refresh_scan_incremental(
+    app: AppHandle,
+    path: String,
+    scan_id: Option<String>,
+) -> Result<IncrementalScan, String> {
+    let key = normalize_path(&path);
+
+    // Any previous tree will do as a baseline, however old. The rescan keeps
+    // the options it was built with.
+    let previous = {
+        let cache = SCAN_CACHE.lock().map_err(|e| e.to_string())?;
+        cache.get(&key).map(|entry| (entry.node.clone(), entry.options.clone(), entry.dir_sizes.clone()))
+    };
+    let (previous, options, dir_sizes) = match previous {
+        Some((node, options, dir_sizes)) if node.children.is_some() => (node, options, dir_sizes),
+        _ => {
+            let node = scan_dir_internal(app, path, ScanOptions::default(), true, scan_id).await?;
+            return Ok(IncrementalScan {
+                changed: vec![NodeChange {
+                    path: node.path.clone(),
+                    kind: ChangeKind::Added,
+                    old_size: 0,
+                    new_size: node.size,
+                }],
+                reused_subtrees: 0,
+                node,
+            });
+        }
+    };
+
+    let scan = start_scan_tracking(&app, &path, scan_id)?;
+    let (stats, cancel_token) = (scan.stats.clone(), scan.cancel.clone());
+
+    let scan_options = options.clone();
+    let sizes = dir_sizes.clone();
+    let result = tauri::async_runtime::spawn_blocking(move || {
+        scan_directory_incremental(&previous, &scan_options, sizes, Some(stats), Some(cancel_token))
+    }).await.map_err(|e| e.to_string())??;
+
+    drop(scan);
+
+    store_scan_result(&key, &result.node, &options, dir_sizes)?;
+
+    Ok(result)
+}
+
+async fn scan_dir_internal(
+    app: AppHandle,
+    path: String,
+    options: ScanOptions,
+    force_refresh: bool,
+    scan_id: Option<String>,
+) -> Result<FileNode, String> {
     let key = normalize_path(&path);
 
     // Check cache
+    let mut baseline = None;
     if !force_refresh {
         let cache = SCAN_CACHE.lock().map_err(|e| e.to_string())?;
         if let Some(entry) = cache.get(&key) {
-            if let Ok(elapsed) = entry.timestamp.elapsed() {
-                if elapsed.as_secs() < CACHE_TTL {
-                    return Ok(entry.node.clone());
-                }
+            if entry.options == options && is_cache_valid(entry) {
+                return Ok(entry.node.clone());
+            }
+            // Lazy revalidation of a tree from the scan index: directories
+            // whose mtime/inode (or subtree stamp, for deep aggregates) still
+            // match are reused rather than walked again.
+            if entry.options == options && entry.needs_rescan && entry.node.children.is_some() {
+                baseline = Some((entry.node.clone(), entry.dir_sizes.clone()));
             }
         }
     }
 
-    // Reset cancellation
+    let scan = start_scan_tracking(&app, &path, scan_id)?;
+    let (stats, cancel_token) = (scan.stats.clone(), scan.cancel.clone());
+
+    if let Some((previous, dir_sizes)) = baseline {
+        let scan_options = options.clone();
+        let sizes = dir_sizes.clone();
+        let result = tauri::async_runtime::spawn_blocking(move || {
+            scan_directory_incremental(&previous, &scan_options, sizes, Some(stats), Some(cancel_token))
+        }).await.map_err(|e| e.to_string())??;
+
+        drop(scan);
+
+        store_scan_result(&key, &result.node, &options, dir_sizes)?;
+
+        return Ok(result.node);
+    }
+
+    let path_clone = path.clone();
+    let scan_options = options.clone();
+    let dir_sizes = Arc::new(DirSizes::default());
+    let sizes = dir_sizes.clone();
+    let result = tauri::async_runtime::spawn_blocking(move || {
+        scan_directory_with_sizes(&path_clone, &scan_options, sizes, Some(stats), Some(cancel_token))
+    }).await.map_err(|e| e.to_string())??;
+
+    drop(scan);
+    
+    store_scan_result(&key, &result, &options, dir_sizes)?;
+
+    Ok(result)
+}
+
+// What a scan of `path` is expected to total, for the ETA: the previous scan
+// of the same path if one is cached, otherwise the volume's used space when
+// `path` is a mount point. Subdirectories of a volume get no estimate.
+fn expected_scan_size(path: &str) -> Option<u64> {
+    if let Some((node, _)) = cached_tree(path) {
+        if node.size > 0 {
+            return Some(node.size);
+        }
+    }
+    let target = Path::new(path);
+    Disks::new_with_refreshed_list()
+        .iter()
+        .find(|d| d.mount_point() == target)
+        .map(|d| d.total_space().saturating_sub(d.available_space()))
+        .filter(|used| *used > 0)
+}
+
+// A registered scan. Dropping it — on success, error or cancellation —
+// stops its progress emitter and frees the scan id.
+struct ScanTracking {
+    id: String,
+    stats: Arc<ScanStats>,
+    cancel: Arc<AtomicBool>,
+    done: Arc<AtomicBool>,
+}
+
+impl Drop for ScanTracking {
+    fn drop(&mut self) {
+        self.done.store(true, Ordering::Relaxed);
+        if let Ok(mut running) = RUNNING_SCANS.lock() {
+            running.remove(&self.id);
+        }
+    }
+}
+
+// Registers a new scan and spawns its `scan-progress` emitter.
+fn start_scan_tracking(app: &AppHandle, path: &str, scan_id: Option<String>) -> Result<ScanTracking, String> {
+    let id = scan_id.unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
     let cancel_token = Arc::new(AtomicBool::new(false));
-    if let Ok(mut state) = SCAN_STATE.write() {
-        state.cancel_token = cancel_token.clone();
+    {
+        let mut running = RUNNING_SCANS.lock().map_err(|e| e.to_string())?;
+        if running.contains_key(&id) {
+            return Err(format!("Scan id {} is already in use", id));
+        }
+        running.insert(id.clone(), cancel_token.clone());
     }
 
     // Stats for progress
-    let stats = Arc::new(ScanStats {
-        scanned_files: AtomicU64::new(0),
-        total_size: AtomicU64::new(0),
-        errors: AtomicU64::new(0),
-    });
+    let stats = Arc::new(ScanStats::new());
 
     let is_done = Arc::new(AtomicBool::new(false));
 
+    let _ = app.emit("scan-started", ScanStarted { scan_id: id.clone(), path: path.to_string() });
+
     // Spawn progress emitter
     let stats_clone = stats.clone();
     let app_handle = app.clone();
-    let path_report = path.clone();
+    let path_report = path.to_string();
+    let id_report = id.clone();
     let cancel_clone = cancel_token.clone();
     let is_done_clone = is_done.clone();
     
     tauri::async_runtime::spawn(async move {
+        let expected_path = path_report.clone();
+        let expected_size = tauri::async_runtime::spawn_blocking(move || expected_scan_size(&expected_path))
+            .await
+            .ok()
+            .flatten();
+        let mut samples: std::collections::VecDeque<(std::time::Instant, u64, u64)> =
+            std::collections::VecDeque::new();
+
         // Emit every 100ms
         loop {
             // Check BEFORE sleeping to avoid emitting after done
@@ -112,12 +370,35 @@ async fn scan_dir_internal(app: AppHandle, path: String, force_refresh: bool) ->
             let count = stats_clone.scanned_files.load(Ordering::Relaxed);
             let size = stats_clone.total_size.load(Ordering::Relaxed);
             let errors = stats_clone.errors.load(Ordering::Relaxed);
+            let current_path = stats_clone.current_dir.lock().map(|p| p.clone()).unwrap_or_default();
+
+            let now = std::time::Instant::now();
+            samples.push_back((now, count, size));
+            while samples.len() > 2 && now.duration_since(samples[0].0) > RATE_WINDOW {
+                samples.pop_front();
+            }
+            let (then, then_count, then_size) = samples[0];
+            let elapsed = now.duration_since(then).as_secs_f64();
+            let (files_per_sec, bytes_per_sec) = if elapsed > 0.0 {
+                ((count - then_count) as f64 / elapsed, (size - then_size) as f64 / elapsed)
+            } else {
+                (0.0, 0.0)
+            };
+            let eta_secs = expected_size
+                .filter(|expected| *expected > size && bytes_per_sec > 0.0)
+                .map(|expected| ((expected - size) as f64 / bytes_per_sec).ceil() as u64);
 
             let payload = ScanProgress {
+                 scan_id: id_report.clone(),
                  path: path_report.clone(),
                  count,
                  size,
-                 errors
+                 errors,
+                 current_path,
+                 files_per_sec,
+                 bytes_per_sec,
+                 expected_size,
+                 eta_secs,
             };
             let _ = app_handle.emit("scan-progress", payload);
 
@@ -125,33 +406,198 @@ async fn scan_dir_internal(app: AppHandle, path: String, force_refresh: bool) ->
         }
     });
 
-    let path_clone = path.clone();
-    let result = tauri::async_runtime::spawn_blocking(move || {
-        scan_directory(&path_clone, Some(stats), Some(cancel_token))
-    }).await.map_err(|e| e.to_string())??;
+    Ok(ScanTracking { id, stats, cancel: cancel_token, done: is_done })
+}
 
-    is_done.store(true, Ordering::Relaxed);
-    
-    // Update cache
-    let mut cache = SCAN_CACHE.lock().map_err(|e| e.to_string())?;
+// Caches a fresh scan (root plus every subdirectory that carries a children
+// list) in memory and mirrors it to the persistent scan index.
+fn store_scan_result(
+    key: &str,
+    result: &FileNode,
+    options: &ScanOptions,
+    dir_sizes: Arc<DirSizes>,
+) -> Result<(), String> {
     let now = SystemTime::now();
-    
-    cache.insert(key.clone(), CacheEntry {
+    let scanned_at = now.duration_since(SystemTime::UNIX_EPOCH).unwrap_or_default().as_secs();
+    let mut indexed = vec![IndexedEntry {
+        key: key.to_string(),
         node: result.clone(),
-        timestamp: now,
-    });
-    
-    if let Some(children) = &result.children {
-        for child in children {
-            let child_key = normalize_path(&child.path);
-            cache.insert(child_key, CacheEntry {
-                node: child.clone(),
+        options: options.clone(),
+        scanned_at,
+    }];
+    let mut listed = Vec::new();
+    for child in result.children.iter().flatten() {
+        collect_listed_dirs(child, &mut listed);
+    }
+    for dir in listed {
+        indexed.push(IndexedEntry {
+            key: normalize_path(&dir.path),
+            node: FileNode { size_mode: Some(options.size_mode), ..dir.clone() },
+            options: options.clone(),
+            scanned_at,
+        });
+    }
+
+    {
+        let mut cache = SCAN_CACHE.lock().map_err(|e| e.to_string())?;
+        for entry in &indexed {
+            cache.insert(entry.key.clone(), CacheEntry {
+                node: entry.node.clone(),
+                options: entry.options.clone(),
                 timestamp: now,
+                dir_sizes: dir_sizes.clone(),
+                needs_rescan: false,
             });
         }
     }
 
-    Ok(result)
+    // Writing a large tree to SQLite shouldn't hold up the UI.
+    tauri::async_runtime::spawn_blocking(move || persist_entries(indexed));
+
+    Ok(())
+}
+
+// Directories of `node`'s subtree that carry a children list, `node` first.
+fn collect_listed_dirs<'a>(node: &'a FileNode, out: &mut Vec<&'a FileNode>) {
+    if node.is_dir && node.children.is_some() {
+        out.push(node);
+        for child in node.children.iter().flatten() {
+            collect_listed_dirs(child, out);
+        }
+    }
+}
+
+/// Give a collapsed directory of a cached tree its children list, so the UI
+/// can drill below the scan depth. Subdirectories are sized from the totals
+/// recorded during the scan, so only the directory itself is listed; totals
+/// that went stale (or were never recorded, e.g. after a restart) are walked.
+/// The expanded node is written back into every cached tree containing it.
+#[command]
+pub async fn expand_node(path: String) -> Result<FileNode, String> {
+    let key = normalize_path(&path);
+    let target = Path::new(&key).to_path_buf();
+    let (node, options, dir_sizes) = cached_node(&target)
+        .ok_or_else(|| "Path has not been scanned yet".to_string())?;
+    if !node.is_dir {
+        return Err("Not a directory".to_string());
+    }
+    if node.children.is_some() {
+        return Ok(FileNode { size_mode: Some(options.size_mode), ..node });
+    }
+
+    let expand_options = options.clone();
+    let sizes = dir_sizes.clone();
+    let expand_target = target.clone();
+    let expanded = tauri::async_runtime::spawn_blocking(move || {
+        expand_dir(&expand_target, &expand_options, sizes)
+    }).await.map_err(|e| e.to_string())?;
+
+    // Gone or filtered out since the scan: drop it from the cached trees too.
+    update_cached_trees(&target, expanded.as_ref());
+    let expanded = expanded.ok_or_else(|| "Directory no longer exists".to_string())?;
+    store_scan_result(&key, &expanded, &options, dir_sizes)?;
+
+    Ok(FileNode { size_mode: Some(options.size_mode), ..expanded })
+}
+
+// The node at `target` in the deepest cached tree that lists it, with that
+// tree's options and directory totals.
+fn cached_node(target: &Path) -> Option<(FileNode, ScanOptions, Arc<DirSizes>)> {
+    let cache = SCAN_CACHE.lock().ok()?;
+    cache
+        .iter()
+        .filter(|(k, _)| target.starts_with(k))
+        .filter_map(|(k, entry)| find_node(&entry.node, target).map(|n| (k.len(), n, entry)))
+        .max_by_key(|(depth, ..)| *depth)
+        .map(|(_, n, entry)| (n.clone(), entry.options.clone(), entry.dir_sizes.clone()))
+}
+
+/// Bytes per owning user under `path`. Served from the cached scan when it
+/// was made with `owners` enabled; otherwise `path` is walked once.
+#[command]
+pub async fn get_owner_usage(path: String) -> Result<OwnerReport, String> {
+    let key = normalize_path(&path);
+    let cached = cached_node(Path::new(&key));
+    if let Some(owners) = cached.as_ref().and_then(|(node, ..)| node.owners.as_ref()) {
+        return Ok(owners::owner_report(&key, owners));
+    }
+
+    // Same filters as the cached scan, if there is one.
+    let options = ScanOptions {
+        owners: true,
+        ..cached.map(|(_, options, _)| options).unwrap_or_default()
+    };
+    let node = tauri::async_runtime::spawn_blocking(move || {
+        measure_path(Path::new(&key), &options).map(|node| (key, node))
+    }).await.map_err(|e| e.to_string())?;
+    match node {
+        Some((root, node)) if node.is_dir => {
+            Ok(owners::owner_report(&root, &node.owners.unwrap_or_default()))
+        }
+        Some(_) => Err("Not a directory".to_string()),
+        None => Err("Directory does not exist".to_string()),
+    }
+}
+
+/// The cached tree for `path` and the options it was scanned with, if any.
+pub(crate) fn cached_tree(path: &str) -> Option<(FileNode, ScanOptions)> {
+    let cache = SCAN_CACHE.lock().ok()?;
+    cache.get(&normalize_path(path)).map(|entry| (entry.node.clone(), entry.options.clone()))
+}
+
+// The cached tree for `path`, its options and when it was scanned, for
+// commands that record or publish a scan as of that time. Stale entries
+// (past the TTL, or loaded from the scan index and not yet revalidated) are
+// refused rather than passed off as current.
+fn current_tree(path: &str) -> Result<(FileNode, ScanOptions, u64), String> {
+    let cache = SCAN_CACHE.lock().map_err(|e| e.to_string())?;
+    let entry = cache.get(&normalize_path(path)).ok_or_else(|| "Path has not been scanned yet".to_string())?;
+    if !is_cache_valid(entry) {
+        return Err("The scan of this path is out of date; rescan it first".to_string());
+    }
+    let scanned_at = entry.timestamp.duration_since(SystemTime::UNIX_EPOCH).unwrap_or_default().as_secs();
+    Ok((entry.node.clone(), entry.options.clone(), scanned_at))
+}
+
+/// Apply a single filesystem change to every cached tree that contains
+/// `target` (see scanner::apply_node_update). Returns the keys updated.
+pub(crate) fn update_cached_trees(target: &Path, update: Option<&FileNode>) -> Vec<String> {
+    let mut indexed = Vec::new();
+    if let Ok(mut cache) = SCAN_CACHE.lock() {
+        for (key, entry) in cache.iter_mut() {
+            if !target.starts_with(key) {
+                continue;
+            }
+            if apply_node_update(&mut entry.node, target, update).is_some() {
+                indexed.push(IndexedEntry {
+                    key: key.clone(),
+                    node: entry.node.clone(),
+                    options: entry.options.clone(),
+                    scanned_at: entry.timestamp
+                        .duration_since(SystemTime::UNIX_EPOCH)
+                        .unwrap_or_default()
+                        .as_secs(),
+                });
+            }
+        }
+    }
+    let keys = indexed.iter().map(|e| e.key.clone()).collect();
+    persist_entries(indexed);
+    keys
+}
+
+/// Where a change at `target` can be applied in each cached tree that
+/// contains it (see scanner::update_point).
+pub(crate) fn cached_update_points(target: &Path) -> Vec<std::path::PathBuf> {
+    let mut points = Vec::new();
+    if let Ok(cache) = SCAN_CACHE.lock() {
+        for (key, entry) in cache.iter() {
+            if target.starts_with(key) {
+                points.extend(update_point(&entry.node, target));
+            }
+        }
+    }
+    points
 }
 
 #[command]
@@ -159,6 +605,225 @@ pub fn clear_cache() {
     if let Ok(mut cache) = SCAN_CACHE.lock() {
         cache.clear();
     }
+    if let Some(index) = SCAN_INDEX.as_ref() {
+        if let Err(e) = index.clear() {
+            log::warn!("Failed to clear scan index: {}", e);
+        }
+    }
+}
+
+/// Drop cached entries affected by a change at `path`: the path itself, its
+/// descendants, and every ancestor (whose aggregated sizes are now wrong).
+fn invalidate_path(path: &str) {
+    let target = normalize_path(path);
+    let target_path = Path::new(&target);
+    let mut removed = Vec::new();
+    if let Ok(mut cache) = SCAN_CACHE.lock() {
+        cache.retain(|key, _| {
+            let key_path = Path::new(key);
+            let affected = target_path.starts_with(key_path) || key_path.starts_with(target_path);
+            if affected {
+                removed.push(key.clone());
+            }
+            !affected
+        });
+    }
+    if let Some(index) = SCAN_INDEX.as_ref() {
+        if let Err(e) = index.remove(&removed) {
+            log::warn!("Failed to update scan index: {}", e);
+        }
+    }
+}
+
+fn scan_index() -> Result<&'static ScanIndex, String> {
+    SCAN_INDEX.as_ref().ok_or_else(|| "Scan index is unavailable".to_string())
+}
+
+/// Save the cached scan of `path` as snapshot `name`, replacing any earlier
+/// snapshot of the same root with that name. The snapshot is dated by the
+/// scan, and an out-of-date scan is refused.
+#[command]
+pub async fn save_scan_snapshot(path: String, name: String) -> Result<SnapshotInfo, String> {
+    let name = name.trim().to_string();
+    if name.is_empty() {
+        return Err("Snapshot name is required".to_string());
+    }
+    let (tree, options, created_at) = current_tree(&path)?;
+    let root = normalize_path(&path);
+
+    tauri::async_runtime::spawn_blocking(move || {
+        scan_index()?.save_snapshot(&name, &root, &options, &tree, created_at)
+    }).await.map_err(|e| e.to_string())?
+}
+
+#[command]
+pub fn list_scan_snapshots(root: Option<String>) -> Result<Vec<SnapshotInfo>, String> {
+    let root = root.map(|r| normalize_path(&r));
+    scan_index()?.list_snapshots(root.as_deref())
+}
+
+#[command]
+pub fn delete_scan_snapshot(id: i64) -> Result<(), String> {
+    scan_index()?.delete_snapshot(id)
+}
+
+/// What changed between two snapshots of the same root, biggest byte delta
+/// first.
+#[command]
+pub async fn diff_scan_snapshots(old_id: i64, new_id: i64) -> Result<SnapshotDiff, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let index = scan_index()?;
+        let (old, old_options, old_tree) = index.load_snapshot(old_id)?;
+        let (new, new_options, new_tree) = index.load_snapshot(new_id)?;
+        if old.root != new.root {
+            return Err(format!("Snapshots are of different roots: {} and {}", old.root, new.root));
+        }
+        if old_options.size_mode != new_options.size_mode {
+            return Err("Snapshots were scanned with different size modes".to_string());
+        }
+        Ok(SnapshotDiff {
+            total_delta: new.size as i64 - old.size as i64,
+            entries: snapshots::diff_trees(&old_tree, &new_tree),
+            old,
+            new,
+        })
+    }).await.map_err(|e| e.to_string())?
+}
+
+/// Write the cached scan of `path` to `destination` as CSV, JSON or an HTML
+/// treemap report, down to `max_depth` levels below the root. An out-of-date
+/// scan is refused.
+#[command]
+pub async fn export_scan(
+    path: String,
+    format: ExportFormat,
+    destination: String,
+    max_depth: Option<u32>,
+) -> Result<ExportResult, String> {
+    let (tree, _, _) = current_tree(&path)?;
+    tauri::async_runtime::spawn_blocking(move || {
+        scan_export::export_tree(&tree, format, std::path::Path::new(&destination), max_depth)
+    }).await.map_err(|e| e.to_string())?
+}
+
+/// Summarise a scanned tree in the shape the AI context expects. Requires a
+/// prior scan; file types are only filled in if that scan collected them.
+#[command]
+pub fn get_scan_summary(path: String) -> Result<ScanSummary, String> {
+    let (node, _) = cached_tree(&path).ok_or_else(|| "Path has not been scanned yet".to_string())?;
+
+    let mut files = Vec::new();
+    collect_files(&node, &mut files);
+    files.sort_by_key(|f| std::cmp::Reverse(f.size));
+    files.truncate(SUMMARY_LARGEST_FILES);
+
+    Ok(ScanSummary {
+        total_files: node.file_count,
+        total_size: node.size,
+        largest_files: files,
+        file_types: node.file_types.as_ref().map(|ft| ft.bytes_by_category()).unwrap_or_default(),
+        scanned_at: chrono::Utc::now().timestamp(),
+    })
+}
+
+const SUMMARY_LARGEST_FILES: usize = 20;
+
+// Files visible anywhere in the cached tree (deep aggregates have none).
+fn collect_files(node: &FileNode, out: &mut Vec<FileInfo>) {
+    for child in node.children.iter().flatten() {
+        if child.is_dir {
+            collect_files(child, out);
+        } else {
+            out.push(FileInfo { path: child.path.clone(), size: child.size });
+        }
+    }
+}
+
+#[command]
+pub async fn find_duplicates(
+    app: AppHandle,
+    path: String,
+    min_size: Option<u64>,
+    scan_id: Option<String>,
+) -> Result<DuplicateReport, String> {
+    let scan = start_scan_tracking(&app, &path, scan_id)?;
+    let (stats, cancel_token) = (scan.stats.clone(), scan.cancel.clone());
+
+    tauri::async_runtime::spawn_blocking(move || {
+        duplicates::find_duplicates(&path, min_size, Some(stats), Some(cancel_token))
+    }).await.map_err(|e| e.to_string())?
+}
+
+/// The `limit` largest files and directories anywhere under `path`, walking
+/// the whole tree rather than the lookahead-limited scan.
+#[command]
+pub async fn get_largest_items(
+    app: AppHandle,
+    path: String,
+    limit: Option<usize>,
+    filter: Option<LargestFilter>,
+    scan_id: Option<String>,
+) -> Result<LargestReport, String> {
+    let scan = start_scan_tracking(&app, &path, scan_id)?;
+    let (stats, cancel_token) = (scan.stats.clone(), scan.cancel.clone());
+    let filter = filter.unwrap_or_default();
+    // Same filters as the cached scan, if there is one.
+    let scan_options = cached_node(Path::new(&normalize_path(&path)))
+        .map(|(_, options, _)| options)
+        .unwrap_or_default();
+
+    tauri::async_runtime::spawn_blocking(move || {
+        largest::find_largest(&path, limit, &filter, &scan_options, Some(stats), Some(cancel_token))
+    }).await.map_err(|e| e.to_string())?
+}
+
+/// Bytes per last-modified (or last-accessed) age bucket under `path`, plus
+/// the largest subtrees nothing in which has been touched for a while.
+#[command]
+pub async fn get_age_report(
+    app: AppHandle,
+    path: String,
+    options: Option<AgeOptions>,
+    scan_id: Option<String>,
+) -> Result<AgeReport, String> {
+    let scan = start_scan_tracking(&app, &path, scan_id)?;
+    let (stats, cancel_token) = (scan.stats.clone(), scan.cancel.clone());
+    let options = options.unwrap_or_default();
+    // Same filters as the cached scan, if there is one.
+    let scan_options = cached_node(Path::new(&normalize_path(&path)))
+        .map(|(_, options, _)| options)
+        .unwrap_or_default();
+
+    tauri::async_runtime::spawn_blocking(move || {
+        age_report::age_report(&path, &options, &scan_options, Some(stats), Some(cancel_token))
+    }).await.map_err(|e| e.to_string())?
+}
+
+/// Delete the chosen copies from duplicate groups through the cleaner's
+/// deletion path. Every selection is re-verified first, and nothing is
+/// deleted if any of them fails.
+#[command]
+pub async fn delete_duplicates(
+    selections: Vec<DuplicateSelection>,
+    options: cleaner::CleaningOptions,
+) -> Result<cleaner::DeletionResult, String> {
+    let result = tauri::async_runtime::spawn_blocking(move || {
+        for selection in &selections {
+            duplicates::verify_selection(selection)?;
+        }
+        let paths: Vec<String> = selections.into_iter().flat_map(|s| s.remove).collect();
+        let result = cleaner::delete_junk_items_with_options(paths.clone(), options)?;
+        Ok::<_, String>((paths, result))
+    }).await.map_err(|e| e.to_string())??;
+
+    let (paths, result) = result;
+    if result.deleted_count > 0 {
+        for path in &paths {
+            invalidate_path(path);
+        }
+    }
+
+    Ok(result)
 }
 
 #[command]
@@ -225,37 +890,200 @@ pub fn open_file(path: String) {
     }
 }
 
+/// Move `path` to the trash, or delete it outright when `permanent` is set.
+/// Returns the trash entry (None for permanent deletes), which
+/// `restore_trashed_item` takes to undo. Either way the action is recorded
+/// in the audit log.
 #[command]
-pub fn delete_item(path: String) -> Result<(), String> {
+pub fn delete_item(path: String, permanent: Option<bool>) -> Result<Option<TrashedItem>, String> {
     let p = Path::new(&path);
-    if !p.exists() {
+    if std::fs::symlink_metadata(p).is_err() {
         return Err("Path does not exist".to_string());
     }
 
-    if p.is_dir() {
-        std::fs::remove_dir_all(p).map_err(|e| e.to_string())?;
+    let permanent = permanent.unwrap_or(false);
+    let result = if permanent {
+        // A link to a directory is removed itself, never its target.
+        let removed = if p.is_dir() && !p.is_symlink() {
+            std::fs::remove_dir_all(p)
+        } else {
+            std::fs::remove_file(p)
+        };
+        removed.map(|_| None).map_err(|e| e.to_string())
     } else {
-        std::fs::remove_file(p).map_err(|e| e.to_string())?;
+        trash::trash(p).map(Some)
+    };
+    audit_file_operation(
+        if permanent { "delete" } else { "trash" },
+        &path,
+        result.as_ref().ok().and_then(|t| t.as_ref()).map(|t| t.trashed_path.clone()),
+        result.as_ref().err(),
+    );
+    let trashed = result?;
+
+    // Sizes change all the way up the tree, but unrelated scans (and the
+    // persistent index) stay usable.
+    invalidate_path(&path);
+
+    Ok(trashed)
+}
+
+/// Whether `delete_item` can move items to the trash on this platform; when
+/// false only permanent deletes work.
+#[command]
+pub fn trash_supported() -> bool {
+    trash::SUPPORTED
+}
+
+/// Items in the user's trash cans, newest first.
+#[command]
+pub fn list_trashed_items() -> Result<Vec<TrashedItem>, String> {
+    trash::list_trash()
+}
+
+/// Undo a `delete_item`: move the item identified by its `.trashinfo` path
+/// back to where it was.
+#[command]
+pub fn restore_trashed_item(info_path: String) -> Result<TrashedItem, String> {
+    let result = trash::restore(Path::new(&info_path));
+    match &result {
+        Ok(item) => {
+            audit_file_operation("restore", &item.original_path, Some(item.trashed_path.clone()), None);
+            invalidate_path(&item.original_path);
+        }
+        Err(e) => audit_file_operation("restore", &info_path, None, Some(e)),
+    }
+    result
+}
+
+fn audit_file_operation(operation: &str, path: &str, trashed_path: Option<String>, error: Option<&String>) {
+    let event = FileOperationEvent {
+        operation: operation.to_string(),
+        path: path.to_string(),
+        trashed_path,
+        destination: None,
+        removed: None,
+        outcome: if error.is_some() { "error" } else { "ok" }.to_string(),
+        error: error.cloned(),
+    };
+    if let Err(e) = audit_log::log_file_operation(&event) {
+        log::warn!("Failed to write audit log: {}", e);
+    }
+}
+
+/// Pack `paths` into a new tar.gz or zip archive at `destination`, read it
+/// back to verify it, and optionally remove the originals. Progress is
+/// reported as `file-op-progress` events; `cancel_file_operation(op_id)`
+/// stops it and deletes the partial archive.
+#[command]
+pub async fn archive_items(
+    app: AppHandle,
+    paths: Vec<String>,
+    destination: String,
+    format: ArchiveFormat,
+    remove_originals: Option<bool>,
+    op_id: Option<String>,
+) -> Result<FileOpResult, String> {
+    let remove_originals = remove_originals.unwrap_or(false);
+    let result = run_file_operation(app, op_id, move |progress| {
+        file_ops::archive_items(&paths, Path::new(&destination), format, remove_originals, progress)
+    }).await?;
+    record_file_operation("archive", &result);
+    Ok(result)
+}
+
+/// Move `paths` into the directory `destination`, possibly on another
+/// volume. Cross-device moves copy, verify checksums and only then remove
+/// each original; `keep_originals` makes it a copy.
+#[command]
+pub async fn move_items(
+    app: AppHandle,
+    paths: Vec<String>,
+    destination: String,
+    keep_originals: Option<bool>,
+    op_id: Option<String>,
+) -> Result<FileOpResult, String> {
+    let keep_originals = keep_originals.unwrap_or(false);
+    let result = run_file_operation(app, op_id, move |progress| {
+        file_ops::move_items(&paths, Path::new(&destination), keep_originals, progress)
+    }).await?;
+    record_file_operation(if keep_originals { "copy" } else { "move" }, &result);
+    Ok(result)
+}
+
+#[command]
+pub fn cancel_file_operation(op_id: String) {
+    if let Ok(running) = RUNNING_FILE_OPS.lock() {
+        if let Some(token) = running.get(&op_id) {
+            token.store(true, Ordering::Relaxed);
+        }
     }
-    
-    // Invalidate cache for parent or just clear all for safety?
-    // Let's clear for now to be safe as size calc up the tree changes.
-    clear_cache();
-    
-    Ok(())
 }
 
+// Registers the operation for cancellation and runs it on the blocking pool
+// with its progress sent as `file-op-progress` events.
+async fn run_file_operation<F>(app: AppHandle, op_id: Option<String>, operation: F) -> Result<FileOpResult, String>
+where
+    F: FnOnce(&mut file_ops::Progress) -> Result<FileOpResult, String> + Send + 'static,
+{
+    let id = op_id.unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
+    let cancel = Arc::new(AtomicBool::new(false));
+    {
+        let mut running = RUNNING_FILE_OPS.lock().map_err(|e| e.to_string())?;
+        if running.contains_key(&id) {
+            return Err(format!("Operation id {} is already in use", id));
+        }
+        running.insert(id.clone(), cancel.clone());
+    }
+
+    let op_id = id.clone();
+    let result = tauri::async_runtime::spawn_blocking(move || {
+        let report = |p: &FileOpProgress| {
+            let _ = app.emit("file-op-progress", p);
+        };
+        let mut progress = file_ops::Progress::new(&op_id, &cancel, &report);
+        operation(&mut progress)
+    }).await.map_err(|e| e.to_string());
+
+    if let Ok(mut running) = RUNNING_FILE_OPS.lock() {
+        running.remove(&id);
+    }
+    result?
+}
+
+// Audit-logs every item and drops the affected cached scans.
+fn record_file_operation(operation: &str, result: &FileOpResult) {
+    for item in &result.items {
+        let event = FileOperationEvent {
+            operation: operation.to_string(),
+            path: item.source.clone(),
+            trashed_path: None,
+            destination: item.destination.clone(),
+            removed: Some(item.removed),
+            outcome: if item.error.is_some() { "error" } else { "ok" }.to_string(),
+            error: item.error.clone(),
+        };
+        if let Err(e) = audit_log::log_file_operation(&event) {
+            log::warn!("Failed to write audit log: {}", e);
+        }
+        if item.removed {
+            invalidate_path(&item.source);
+        }
+    }
+    invalidate_path(&result.destination);
+}
+
+/// Mounted volumes as top-level nodes for the explorer, each carrying its
+/// device, filesystem type, space usage and read-only flag in `drive`.
+/// tmpfs, overlay and squashfs mounts are hidden unless `include_pseudo`.
 #[command]
-pub fn get_drives() -> Vec<FileNode> {
+pub fn get_drives(include_pseudo: Option<bool>) -> Vec<FileNode> {
     let mut drives = Vec::new();
-    let disks = Disks::new_with_refreshed_list();
 
-    for disk in &disks {
-        let name = disk.name().to_string_lossy().to_string();
-        let mount_point = disk.mount_point().to_string_lossy().to_string();
-        let total = disk.total_space();
-        let available = disk.available_space();
-        let used = total.saturating_sub(available);
+    for drive in mounts::list_drives(include_pseudo.unwrap_or(false)) {
+        let name = drive.label.clone();
+        let mount_point = drive.mount_point.clone();
+        let used = drive.used;
 
         let height_name = if name.is_empty() {
              if mount_point == "/" { 
@@ -284,15 +1112,35 @@ pub fn get_drives() -> Vec<FileNode> {
             name: final_name,
             path: mount_point,
             size: used,
+            apparent_size: used,
+            allocated_size: used,
             is_dir: true,
+            kind: NodeKind::Directory,
+            link_target: None,
             children: None,
             last_modified,
             file_count: 0,
+            inode: None,
+            file_types: None,
+            uid: None,
+            gid: None,
+            mode: None,
+            owners: None,
+            size_mode: None,
+            scan_errors: None,
+            drive: Some(drive),
+            tree_stamp: None,
         });
     }
     drives
 }
 
+/// The cleaner's effective rules: built-ins plus `~/.ittoolkit/cleaning_rules.yaml`.
+#[command]
+pub fn get_cleaning_rules() -> Vec<CleaningRule> {
+    cleaner::get_cleaning_rules()
+}
+
 #[command]
 pub async fn scan_junk() -> Result<Vec<JunkCategory>, String> {
     // This could also be spawned blocking if it takes time
@@ -341,3 +1189,33 @@ pub async fn clean_junk_with_options(
     Ok(result)
 }
 
+/// Package, journal, container and Flatpak/Snap caches that the owning tool
+/// prunes, with what each would reclaim. Linux only; empty elsewhere.
+#[command]
+pub async fn scan_tool_caches(options: Option<cleaner::CleaningOptions>) -> Result<Vec<ToolCategory>, String> {
+    let options = options.unwrap_or_default();
+    tauri::async_runtime::spawn_blocking(move || tool_cleanup::scan_tool_categories(&options))
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Run the tool's own prune command for `tool`, elevated through pkexec when
+/// it needs root. With `options.dry_run` only reports what would run.
+#[command]
+pub async fn prune_tool_cache(tool: CleanupTool, options: Option<cleaner::CleaningOptions>) -> Result<PruneResult, String> {
+    let options = options.unwrap_or_default();
+    let result = tauri::async_runtime::spawn_blocking(move || tool_cleanup::prune(tool, &options))
+        .await
+        .map_err(|e| e.to_string())?;
+
+    if let Ok(pruned) = &result {
+        if !pruned.dry_run && !pruned.commands.is_empty() {
+            let error = (!pruned.success).then(|| pruned.output.clone());
+            for command in &pruned.commands {
+                audit_file_operation("prune", &command.join(" "), None, error.as_ref());
+            }
+        }
+    }
+    result
+}
+
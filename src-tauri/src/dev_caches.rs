@@ -0,0 +1,329 @@
+// Developer toolchain caches and build output for the cleaner.
+//
+// Package-manager caches (cargo, npm, Gradle, Maven, pip, Go) are listed as
+// one item each, looked up where the tool itself keeps them, so CARGO_HOME,
+// GOMODCACHE and friends are honoured. Per-project `target/` and
+// `node_modules` directories are found by walking the roots the user picked
+// in `CleaningOptions::project_roots`; only those next to the matching
+// manifest count, so an unrelated folder named `target` is left alone.
+//
+// Each item carries the newest modification time inside it, directories
+// included, i.e. the last build or install. That is also what the age filter
+// compares against, at scan time and again before deleting: a two-year-old
+// `target/` that was rebuilt yesterday is not stale. Directories matter
+// because npm extracts packages with their published mtimes (often 1985), so
+// only the directories it creates (and `node_modules/.package-lock.json`)
+// date an install.
+
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use crate::cleaner::{CleaningOptions, JunkItem};
+use crate::scanner::modified_secs;
+
+pub const CATEGORY_ID: &str = "developer_cache";
+pub const CATEGORY_NAME: &str = "Developer Caches";
+
+// Deep enough for monorepos, shallow enough to stay quick on a home dir.
+const MAX_PROJECT_DEPTH: usize = 8;
+
+struct ToolCache {
+    name: &'static str,
+    description: &'static str,
+    path: PathBuf,
+}
+
+// Build output directory `dir_name` next to `manifest`.
+struct Artifact {
+    dir_name: &'static str,
+    manifest: &'static str,
+    description: &'static str,
+}
+
+const ARTIFACTS: [Artifact; 3] = [
+    Artifact { dir_name: "target", manifest: "Cargo.toml", description: "Rust build output" },
+    Artifact { dir_name: "target", manifest: "pom.xml", description: "Maven build output" },
+    Artifact { dir_name: "node_modules", manifest: "package.json", description: "Installed npm packages" },
+];
+
+/// Tool caches plus the build output under the chosen project roots that
+/// pass the age filter.
+pub fn scan_developer_caches(options: &CleaningOptions) -> Vec<JunkItem> {
+    let mut items: Vec<JunkItem> = tool_caches()
+        .into_iter()
+        .filter(|cache| cache.path.is_dir())
+        .map(|cache| {
+            let (size, newest) = measure(&cache.path);
+            junk_item(&cache.path, cache.name.to_string(), cache.description, size, newest, None)
+        })
+        .collect();
+    for root in &options.project_roots {
+        items.extend(find_project_artifacts(Path::new(root)));
+    }
+    apply_filters(items, options)
+}
+
+fn apply_filters(mut items: Vec<JunkItem>, options: &CleaningOptions) -> Vec<JunkItem> {
+    if let Some(min_age) = options.min_age_days {
+        items.retain(|i| i.age_days.is_some_and(|age| age >= min_age));
+    }
+    items.retain(|i| i.size > 0);
+    items
+}
+
+/// Whether `path` is an item `scan_developer_caches` lists: a tool cache, or
+/// build output next to its project's manifest.
+pub fn is_developer_cache(path: &Path) -> bool {
+    if tool_caches().iter().any(|cache| cache.path == path) {
+        return true;
+    }
+    let (Some(name), Some(project)) = (path.file_name(), path.parent()) else {
+        return false;
+    };
+    ARTIFACTS.iter().any(|a| name == a.dir_name && project.join(a.manifest).is_file())
+}
+
+/// Age of a developer cache item as the scan reports it: days since the
+/// newest modification anywhere inside it.
+pub fn age_days(path: &Path) -> Option<u32> {
+    measure(path).1.map(days_since)
+}
+
+fn tool_caches() -> Vec<ToolCache> {
+    let home = dirs::home_dir();
+    let cache_dir = dirs::cache_dir();
+    let env_path = |var: &str| std::env::var_os(var).filter(|v| !v.is_empty()).map(PathBuf::from);
+    let in_home = |path: &str| home.as_ref().map(|h| h.join(path));
+
+    let npm = env_path("npm_config_cache").or_else(|| {
+        if cfg!(windows) {
+            dirs::data_local_dir().map(|d| d.join("npm-cache"))
+        } else {
+            in_home(".npm")
+        }
+    });
+    let pip = env_path("PIP_CACHE_DIR").or_else(|| {
+        let dir = cache_dir.as_ref().map(|d| d.join("pip"));
+        if cfg!(windows) { dir.map(|d| d.join("Cache")) } else { dir }
+    });
+    let gopath = std::env::var_os("GOPATH")
+        .and_then(|p| std::env::split_paths(&p).next())
+        .filter(|p| !p.as_os_str().is_empty())
+        .or_else(|| in_home("go"));
+
+    let caches = [
+        (
+            "Cargo registry",
+            "Downloaded Rust crates",
+            env_path("CARGO_HOME").or_else(|| in_home(".cargo")).map(|p| p.join("registry")),
+        ),
+        ("npm cache", "npm package cache", npm.map(|p| p.join("_cacache"))),
+        (
+            "Gradle caches",
+            "Gradle dependency and build cache",
+            env_path("GRADLE_USER_HOME").or_else(|| in_home(".gradle")).map(|p| p.join("caches")),
+        ),
+        ("Maven repository", "Downloaded Maven artifacts", in_home(".m2").map(|p| p.join("repository"))),
+        ("pip cache", "Python pip cache", pip),
+        (
+            "Go module cache",
+            "Downloaded Go modules",
+            env_path("GOMODCACHE").or_else(|| gopath.map(|p| p.join("pkg").join("mod"))),
+        ),
+        (
+            "Go build cache",
+            "Go compiler cache",
+            env_path("GOCACHE").or_else(|| cache_dir.as_ref().map(|d| d.join("go-build"))),
+        ),
+    ];
+    caches
+        .into_iter()
+        .filter_map(|(name, description, path)| path.map(|path| ToolCache { name, description, path }))
+        .collect()
+}
+
+// Walks `root` for build output directories, without descending into them or
+// into hidden directories.
+fn find_project_artifacts(root: &Path) -> Vec<JunkItem> {
+    let mut items = Vec::new();
+    let mut walker = walkdir::WalkDir::new(root).max_depth(MAX_PROJECT_DEPTH).into_iter();
+    while let Some(entry) = walker.next() {
+        let entry = match entry {
+            Ok(e) => e,
+            Err(_) => continue,
+        };
+        if !entry.file_type().is_dir() || entry.depth() == 0 {
+            continue;
+        }
+        let name = entry.file_name().to_string_lossy();
+        if name.starts_with('.') {
+            walker.skip_current_dir();
+            continue;
+        }
+        if !ARTIFACTS.iter().any(|a| a.dir_name == name) {
+            continue;
+        }
+        walker.skip_current_dir();
+
+        let project = match entry.path().parent() {
+            Some(p) => p,
+            None => continue,
+        };
+        if let Some(artifact) = ARTIFACTS.iter().find(|a| a.dir_name == name && project.join(a.manifest).is_file()) {
+            let (size, newest) = measure(entry.path());
+            items.push(junk_item(
+                entry.path(),
+                project_name(project, artifact.manifest),
+                artifact.description,
+                size,
+                newest,
+                Some(project),
+            ));
+        }
+    }
+    items
+}
+
+// Name from the project's manifest, else its directory name.
+fn project_name(project: &Path, manifest: &str) -> String {
+    let content = std::fs::read_to_string(project.join(manifest)).unwrap_or_default();
+    let name = match manifest {
+        "Cargo.toml" => cargo_package_name(&content),
+        "package.json" => {
+            #[derive(Deserialize)]
+            struct Package {
+                name: Option<String>,
+            }
+            serde_json::from_str::<Package>(&content).ok().and_then(|p| p.name)
+        }
+        _ => None,
+    };
+    name.filter(|n| !n.is_empty())
+        .unwrap_or_else(|| project.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default())
+}
+
+// `name = "..."` in the [package] table; workspaces have none.
+fn cargo_package_name(manifest: &str) -> Option<String> {
+    let mut in_package = false;
+    for line in manifest.lines().map(str::trim) {
+        if line.starts_with('[') {
+            in_package = line == "[package]";
+        } else if in_package {
+            if let Some(value) = line.strip_prefix("name").map(str::trim_start).and_then(|l| l.strip_prefix('=')) {
+                return Some(value.trim().trim_matches('"').to_string());
+            }
+        }
+    }
+    None
+}
+
+// Total file size and newest modification time of any file or directory,
+// in seconds since the Unix epoch. Links are not followed.
+fn measure(path: &Path) -> (u64, Option<u64>) {
+    let mut size = 0;
+    let mut newest = None;
+    for entry in walkdir::WalkDir::new(path).into_iter().flatten() {
+        if let Ok(meta) = entry.metadata() {
+            if !meta.is_dir() {
+                size += meta.len();
+            }
+            newest = newest.max(Some(modified_secs(&meta)));
+        }
+    }
+    (size, newest)
+}
+
+fn days_since(secs: u64) -> u32 {
+    let now = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    (now.saturating_sub(secs) / 86_400) as u32
+}
+
+fn junk_item(
+    path: &Path,
+    name: String,
+    description: &str,
+    size: u64,
+    newest: Option<u64>,
+    project: Option<&Path>,
+) -> JunkItem {
+    JunkItem {
+        path: path.to_string_lossy().to_string(),
+        name,
+        size,
+        description: description.to_string(),
+        age_days: newest.map(days_since),
+        project: project.map(|p| p.to_string_lossy().to_string()),
+        last_build: newest,
+        log_kind: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TempDir;
+    use std::fs;
+    use std::time::Duration;
+
+    fn set_age(path: &Path, days: u64) {
+        let mtime = SystemTime::now() - Duration::from_secs(days * 86_400);
+        fs::File::open(path).unwrap().set_modified(mtime).unwrap();
+    }
+
+    fn write_aged(path: &Path, days: u64) {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, b"build output").unwrap();
+        set_age(path, days);
+    }
+
+    // Ages every directory in `root`, deepest first so parents keep theirs.
+    fn age_dirs(root: &Path, days: u64) {
+        for entry in walkdir::WalkDir::new(root).contents_first(true).into_iter().flatten() {
+            if entry.file_type().is_dir() {
+                set_age(entry.path(), days);
+            }
+        }
+    }
+
+    #[test]
+    fn finds_project_output_with_owner_and_age() {
+        let root = TempDir::new("devcache");
+        fs::create_dir_all(root.join("web")).unwrap();
+        fs::create_dir_all(root.join("rusty")).unwrap();
+        fs::write(root.join("rusty/Cargo.toml"), "[package]\nname = \"rusty-app\"\n[dependencies]\nname = 1\n").unwrap();
+        write_aged(&root.join("rusty/target/debug/app"), 90);
+        write_aged(&root.join("rusty/target/debug/deps/old"), 120);
+        age_dirs(&root.join("rusty/target"), 90);
+        // npm keeps the published 1985 mtimes; the install is dated by the
+        // directories it created just now.
+        fs::write(root.join("web/package.json"), r#"{"name": "web-ui"}"#).unwrap();
+        write_aged(&root.join("web/node_modules/left-pad/index.js"), 15_000);
+        write_aged(&root.join("web/node_modules/left-pad/node_modules/x/index.js"), 15_000);
+        write_aged(&root.join("notes/target/report.txt"), 400);
+        write_aged(&root.join(".hidden/Cargo.toml"), 400);
+        write_aged(&root.join(".hidden/target/x"), 400);
+
+        let mut items = find_project_artifacts(&root);
+        items.sort_by(|a, b| a.name.cmp(&b.name));
+        let names: Vec<&str> = items.iter().map(|i| i.name.as_str()).collect();
+        assert_eq!(names, vec!["rusty-app", "web-ui"]);
+        assert_eq!(items[0].age_days, Some(90));
+        assert_eq!(items[0].project.as_deref(), Some(root.join("rusty").to_str().unwrap()));
+        assert_eq!(items[1].size, 24);
+        assert_eq!(items[1].age_days, Some(0));
+
+        let options = CleaningOptions { min_age_days: Some(30), ..Default::default() };
+        let stale = apply_filters(items, &options);
+        assert_eq!(stale.len(), 1);
+        assert!(stale[0].path.ends_with("target"));
+
+        // Deleting re-checks age with the same measure.
+        assert!(is_developer_cache(&root.join("rusty/target")));
+        assert!(!is_developer_cache(&root.join("notes/target")));
+        assert_eq!(age_days(&root.join("rusty/target")), Some(90));
+    }
+}
@@ -0,0 +1,326 @@
+// Duplicate file detection for the storage analyzer.
+//
+// Three passes, each narrowing the candidate set before paying for the next:
+//   1. group every regular file under the root by exact size (one walk);
+//   2. within same-size groups, hash the first and last PARTIAL_BYTES;
+//   3. within same-partial-hash groups, hash the full content.
+// Hashing is parallelised with rayon. Hard links to the same inode are
+// collapsed first — deleting one of them frees nothing.
+
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{HashMap, HashSet};
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+use crate::scanner::ScanStats;
+
+const PARTIAL_BYTES: u64 = 4096;
+const DEFAULT_MIN_SIZE: u64 = 1024;
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct DuplicateGroup {
+    pub size: u64,
+    /// Hex SHA-256 of the full content.
+    pub hash: String,
+    pub paths: Vec<String>,
+    /// Bytes freed by keeping one copy and deleting the rest.
+    pub reclaimable_bytes: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct DuplicateReport {
+    pub root: String,
+    pub groups: Vec<DuplicateGroup>,
+    pub total_reclaimable: u64,
+    pub files_scanned: u64,
+}
+
+/// Which copy of a group to keep and which to delete.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct DuplicateSelection {
+    pub keep: String,
+    pub remove: Vec<String>,
+}
+
+pub fn find_duplicates(
+    root: &str,
+    min_size: Option<u64>,
+    stats: Option<Arc<ScanStats>>,
+    cancel: Option<Arc<AtomicBool>>,
+) -> Result<DuplicateReport, String> {
+    let root_path = Path::new(root);
+    if !root_path.is_dir() {
+        return Err("Directory does not exist".to_string());
+    }
+    let min_size = min_size.unwrap_or(DEFAULT_MIN_SIZE).max(1);
+    let is_cancelled = || cancel.as_ref().map(|c| c.load(Ordering::Relaxed)).unwrap_or(false);
+
+    // Pass 1: group by size.
+    let mut by_size: HashMap<u64, Vec<PathBuf>> = HashMap::new();
+    let mut seen_inodes = std::collections::HashSet::new();
+    let mut files_scanned = 0u64;
+
+    for (idx, entry) in walkdir::WalkDir::new(root_path).min_depth(1).into_iter().enumerate() {
+        if idx % 100 == 0 && is_cancelled() {
+            return Err("Cancelled".to_string());
+        }
+        let entry = match entry {
+            Ok(e) => e,
+            Err(_) => {
+                if let Some(st) = &stats {
+                    st.errors.fetch_add(1, Ordering::Relaxed);
+                }
+                continue;
+            }
+        };
+        if entry.file_type().is_dir() {
+            if let Some(st) = &stats {
+                st.set_current_dir(entry.path());
+            }
+        }
+        if !entry.file_type().is_file() {
+            continue;
+        }
+        let meta = match entry.metadata() {
+            Ok(m) => m,
+            Err(_) => continue,
+        };
+        files_scanned += 1;
+        if let Some(st) = &stats {
+            st.scanned_files.fetch_add(1, Ordering::Relaxed);
+            st.total_size.fetch_add(meta.len(), Ordering::Relaxed);
+        }
+        if meta.len() < min_size {
+            continue;
+        }
+        if let Some(id) = file_id(&meta) {
+            if !seen_inodes.insert(id) {
+                continue;
+            }
+        }
+        by_size.entry(meta.len()).or_default().push(entry.into_path());
+    }
+
+    let candidates: Vec<(u64, Vec<PathBuf>)> = by_size
+        .into_iter()
+        .filter(|(_, paths)| paths.len() > 1)
+        .collect();
+
+    // Pass 2 + 3: partial then full hash, one size group per rayon task.
+    let groups: Result<Vec<Vec<DuplicateGroup>>, String> = candidates
+        .into_par_iter()
+        .map(|(size, paths)| {
+            if is_cancelled() {
+                return Err("Cancelled".to_string());
+            }
+            let mut groups = Vec::new();
+            for same_partial in split_by(paths, |p| partial_hash(p, size)) {
+                for (hash, same_full) in split_by_keyed(same_partial, full_hash) {
+                    groups.push(DuplicateGroup {
+                        size,
+                        hash,
+                        reclaimable_bytes: size * (same_full.len() as u64 - 1),
+                        paths: same_full.iter().map(|p| p.to_string_lossy().to_string()).collect(),
+                    });
+                }
+            }
+            Ok(groups)
+        })
+        .collect();
+
+    let mut groups: Vec<DuplicateGroup> = groups?.into_iter().flatten().collect();
+    groups.sort_by_key(|g| std::cmp::Reverse(g.reclaimable_bytes));
+    let total_reclaimable = groups.iter().map(|g| g.reclaimable_bytes).sum();
+
+    Ok(DuplicateReport {
+        root: root.to_string(),
+        groups,
+        total_reclaimable,
+        files_scanned,
+    })
+}
+
+/// Check a selection before deleting anything: the kept copy must exist, no
+/// removed copy may be the kept file (or another removed one) under a
+/// different name, and every removed copy must still be byte-identical to it
+/// (files may have changed since the scan).
+pub fn verify_selection(selection: &DuplicateSelection) -> Result<(), String> {
+    // Paths are compared as files, not strings: `a/./f`, `a/../a/f`, a
+    // symlinked parent or a hard link all reach the same content, and
+    // deleting one of them deletes the only copy.
+    let (keep, keep_id) = identify(&selection.keep)
+        .ok_or_else(|| format!("Kept copy is no longer readable: {}", selection.keep))?;
+    let keep_hash = full_hash(&keep)
+        .ok_or_else(|| format!("Kept copy is no longer readable: {}", selection.keep))?;
+    let mut seen = HashSet::from([keep_id]);
+    for path in &selection.remove {
+        let (canonical, id) = identify(path).ok_or_else(|| format!("{} is no longer readable", path))?;
+        if !seen.insert(id) {
+            return Err(format!("{} is the same file as another selected copy", path));
+        }
+        if full_hash(&canonical).as_deref() != Some(keep_hash.as_str()) {
+            return Err(format!("{} no longer matches {}", path, selection.keep));
+        }
+    }
+    Ok(())
+}
+
+#[derive(PartialEq, Eq, Hash)]
+enum FileIdentity {
+    Inode(u64, u64),
+    Path(PathBuf),
+}
+
+// Canonical path of `path` and what makes it the same file as another: the
+// (dev, inode) pair where there is one, else the canonical path.
+fn identify(path: &str) -> Option<(PathBuf, FileIdentity)> {
+    let canonical = std::fs::canonicalize(path).ok()?;
+    let meta = std::fs::metadata(&canonical).ok()?;
+    let id = match file_id(&meta) {
+        Some((dev, ino)) => FileIdentity::Inode(dev, ino),
+        None => FileIdentity::Path(canonical.clone()),
+    };
+    Some((canonical, id))
+}
+
+// Groups paths by a hash function, dropping unreadable files and singleton groups.
+fn split_by_keyed(paths: Vec<PathBuf>, hash: impl Fn(&Path) -> Option<String>) -> Vec<(String, Vec<PathBuf>)> {
+    let mut buckets: HashMap<String, Vec<PathBuf>> = HashMap::new();
+    for path in paths {
+        if let Some(h) = hash(&path) {
+            buckets.entry(h).or_default().push(path);
+        }
+    }
+    buckets.into_iter().filter(|(_, v)| v.len() > 1).collect()
+}
+
+fn split_by(paths: Vec<PathBuf>, hash: impl Fn(&Path) -> Option<String>) -> Vec<Vec<PathBuf>> {
+    split_by_keyed(paths, hash).into_iter().map(|(_, v)| v).collect()
+}
+
+fn partial_hash(path: &Path, size: u64) -> Option<String> {
+    let mut file = File::open(path).ok()?;
+    let mut hasher = Sha256::new();
+    let mut buf = vec![0u8; PARTIAL_BYTES as usize];
+
+    let n = read_up_to(&mut file, &mut buf)?;
+    hasher.update(&buf[..n]);
+
+    // Files that differ only in a trailer (logs, archives) are common.
+    if size > PARTIAL_BYTES * 2 {
+        file.seek(SeekFrom::Start(size - PARTIAL_BYTES)).ok()?;
+        let n = read_up_to(&mut file, &mut buf)?;
+        hasher.update(&buf[..n]);
+    }
+    Some(to_hex(&hasher.finalize()))
+}
+
+fn full_hash(path: &Path) -> Option<String> {
+    let mut file = File::open(path).ok()?;
+    let mut hasher = Sha256::new();
+    let mut buf = vec![0u8; 64 * 1024];
+    loop {
+        let n = file.read(&mut buf).ok()?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buf[..n]);
+    }
+    Some(to_hex(&hasher.finalize()))
+}
+
+fn read_up_to(file: &mut File, buf: &mut [u8]) -> Option<usize> {
+    let mut filled = 0;
+    while filled < buf.len() {
+        match file.read(&mut buf[filled..]) {
+            Ok(0) => break,
+            Ok(n) => filled += n,
+            Err(_) => return None,
+        }
+    }
+    Some(filled)
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+#[cfg(unix)]
+fn file_id(meta: &std::fs::Metadata) -> Option<(u64, u64)> {
+    use std::os::unix::fs::MetadataExt;
+    Some((meta.dev(), meta.ino()))
+}
+
+#[cfg(not(unix))]
+fn file_id(_meta: &std::fs::Metadata) -> Option<(u64, u64)> {
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TempDir;
+
+    fn temp_root(name: &str) -> TempDir {
+        let dir = TempDir::new(&format!("dup-{}", name));
+        std::fs::create_dir_all(dir.join("sub")).unwrap();
+        dir
+    }
+
+    #[test]
+    fn groups_identical_files_and_ignores_same_size_different_content() {
+        let root = temp_root("groups");
+        let content = vec![7u8; 10_000];
+        std::fs::write(root.join("a.bin"), &content).unwrap();
+        std::fs::write(root.join("sub/b.bin"), &content).unwrap();
+        let mut other = content.clone();
+        other[9_999] = 8; // differs only in the trailer
+        std::fs::write(root.join("c.bin"), &other).unwrap();
+
+        let report = find_duplicates(root.to_str().unwrap(), Some(1), None, None).unwrap();
+        assert_eq!(report.groups.len(), 1);
+        assert_eq!(report.groups[0].paths.len(), 2);
+        assert_eq!(report.total_reclaimable, 10_000);
+    }
+
+    #[test]
+    fn selection_rejects_changed_copy() {
+        let root = temp_root("verify");
+        std::fs::write(root.join("a"), b"same").unwrap();
+        std::fs::write(root.join("b"), b"diff").unwrap();
+        let selection = DuplicateSelection {
+            keep: root.join("a").to_string_lossy().to_string(),
+            remove: vec![root.join("b").to_string_lossy().to_string()],
+        };
+        assert!(verify_selection(&selection).is_err());
+    }
+
+    #[test]
+    fn selection_rejects_the_kept_file_under_another_name() {
+        let root = temp_root("aliases");
+        std::fs::write(root.join("sub/f"), b"only copy").unwrap();
+        let select = |keep: &str, remove: &[String]| DuplicateSelection {
+            keep: root.join(keep).to_string_lossy().to_string(),
+            remove: remove.to_vec(),
+        };
+        let alias = |p: &str| format!("{}/{}", root.display(), p);
+
+        assert!(verify_selection(&select("sub/f", &[alias("sub/./f")])).is_err());
+        assert!(verify_selection(&select("sub/f", &[alias("sub/../sub/f")])).is_err());
+        #[cfg(unix)]
+        {
+            std::os::unix::fs::symlink(root.join("sub"), root.join("linked")).unwrap();
+            assert!(verify_selection(&select("sub/f", &[alias("linked/f")])).is_err());
+
+            std::fs::write(root.join("g"), b"only copy").unwrap();
+            std::fs::hard_link(root.join("g"), root.join("g2")).unwrap();
+            assert!(verify_selection(&select("sub/f", &[alias("g"), alias("g2")])).is_err());
+            assert!(verify_selection(&select("sub/f", &[alias("g")])).is_ok());
+        }
+    }
+}
@@ -0,0 +1,881 @@
+// Bulk moves and archives for the Storage Analyzer.
+//
+// The "free up space" workflow: pack old projects into a tar.gz or zip, or
+// move them to another volume, and only then remove the originals. Nothing
+// is removed until the copy has been read back and checked against the
+// source listing taken up front (sizes for archives, whose gzip/zip CRCs are
+// checked on read; SHA-256 for copied files), and until the source has been
+// re-walked to make sure nothing in it changed in the meantime.
+//
+// Moves within one filesystem are plain renames. Each selected item succeeds
+// or fails on its own, so one unreadable folder doesn't undo the others.
+
+use chrono::{Datelike, Timelike};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::fs::{self, File, Metadata, OpenOptions};
+use std::io::{self, BufWriter, Read, Write};
+use std::path::{Component, Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+
+use crate::scanner::ownership_of;
+
+const REPORT_INTERVAL: Duration = Duration::from_millis(100);
+const COPY_BUFFER: usize = 256 * 1024;
+
+// rename(2) across filesystems.
+#[cfg(windows)]
+const CROSS_DEVICE: i32 = 17; // ERROR_NOT_SAME_DEVICE
+#[cfg(not(windows))]
+const CROSS_DEVICE: i32 = 18; // EXDEV on Linux and macOS
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+pub enum ArchiveFormat {
+    #[serde(rename = "tar.gz")]
+    TarGz,
+    #[serde(rename = "zip")]
+    Zip,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum FileOpPhase {
+    Measuring,
+    Copying,
+    Archiving,
+    Verifying,
+    Removing,
+}
+
+/// Payload of the `file-op-progress` event. Counters restart with each phase.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct FileOpProgress {
+    pub op_id: String,
+    pub phase: FileOpPhase,
+    pub current_path: String,
+    pub bytes_done: u64,
+    pub bytes_total: u64,
+    pub files_done: u64,
+    pub files_total: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct FileOpItem {
+    pub source: String,
+    /// Where the item ended up: its new path for moves, the archive for
+    /// archives. None if it failed before anything was written.
+    pub destination: Option<String>,
+    pub removed: bool,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct FileOpResult {
+    pub op_id: String,
+    /// The archive file, or the directory items were moved into.
+    pub destination: String,
+    pub items: Vec<FileOpItem>,
+    pub files: u64,
+    pub bytes: u64,
+    /// Archive size on disk; None for moves.
+    pub archive_size: Option<u64>,
+}
+
+// Throttled progress reporting plus the cancellation check.
+pub struct Progress<'a> {
+    state: FileOpProgress,
+    cancel: &'a AtomicBool,
+    report: &'a dyn Fn(&FileOpProgress),
+    last_report: Instant,
+    interval: Duration,
+}
+
+impl<'a> Progress<'a> {
+    pub fn new(op_id: &str, cancel: &'a AtomicBool, report: &'a dyn Fn(&FileOpProgress)) -> Self {
+        Self {
+            state: FileOpProgress {
+                op_id: op_id.to_string(),
+                phase: FileOpPhase::Measuring,
+                current_path: String::new(),
+                bytes_done: 0,
+                bytes_total: 0,
+                files_done: 0,
+                files_total: 0,
+            },
+            cancel,
+            report,
+            last_report: Instant::now(),
+            interval: REPORT_INTERVAL,
+        }
+    }
+
+    fn phase(&mut self, phase: FileOpPhase, files_total: u64, bytes_total: u64) {
+        self.state.phase = phase;
+        self.state.files_total = files_total;
+        self.state.bytes_total = bytes_total;
+        self.state.files_done = 0;
+        self.state.bytes_done = 0;
+        self.emit();
+    }
+
+    fn cancelled(&self) -> bool {
+        self.cancel.load(Ordering::Relaxed)
+    }
+
+    fn check_cancel(&self) -> Result<(), String> {
+        if self.cancelled() {
+            Err("Cancelled".to_string())
+        } else {
+            Ok(())
+        }
+    }
+
+    fn start(&mut self, path: &Path) -> Result<(), String> {
+        self.check_cancel()?;
+        self.state.current_path = path.to_string_lossy().to_string();
+        self.maybe_emit();
+        Ok(())
+    }
+
+    fn add_bytes(&mut self, n: u64) {
+        self.state.bytes_done += n;
+        self.maybe_emit();
+    }
+
+    fn file_done(&mut self) {
+        self.state.files_done += 1;
+    }
+
+    fn maybe_emit(&mut self) {
+        if self.last_report.elapsed() >= self.interval {
+            self.emit();
+        }
+    }
+
+    fn emit(&mut self) {
+        (self.report)(&self.state);
+        self.last_report = Instant::now();
+    }
+}
+
+// Counts bytes into the progress and stops reading once cancelled. The
+// error must not be `Interrupted`: io::copy (and tar, which uses it) retries
+// those, and would spin on a cancelled reader forever.
+struct ProgressReader<'p, 'a, R> {
+    inner: R,
+    progress: &'p mut Progress<'a>,
+    read: u64,
+}
+
+impl<'p, 'a, R> ProgressReader<'p, 'a, R> {
+    fn new(inner: R, progress: &'p mut Progress<'a>) -> Self {
+        Self { inner, progress, read: 0 }
+    }
+}
+
+impl<R: Read> Read for ProgressReader<'_, '_, R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.progress.cancelled() {
+            return Err(io::Error::other("Cancelled"));
+        }
+        let n = self.inner.read(buf)?;
+        self.read += n as u64;
+        self.progress.add_bytes(n as u64);
+        Ok(n)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum EntryKind {
+    File,
+    Dir,
+    Symlink,
+}
+
+// One filesystem entry of a selected item, as listed before anything is
+// written.
+struct Entry {
+    path: PathBuf,
+    /// '/'-separated, starting with the item's own name.
+    name: String,
+    kind: EntryKind,
+    meta: Metadata,
+}
+
+impl Entry {
+    fn size(&self) -> u64 {
+        if self.kind == EntryKind::File { self.meta.len() } else { 0 }
+    }
+
+    // What must not change between listing and removing the original.
+    fn signature(&self) -> (String, u64, Option<std::time::SystemTime>) {
+        (self.name.clone(), self.size(), self.meta.modified().ok())
+    }
+}
+
+struct Item {
+    source: PathBuf,
+    name: String,
+    /// Pre-order, sorted by name, the item itself first.
+    entries: Vec<Entry>,
+}
+
+impl Item {
+    fn files(&self) -> u64 {
+        self.entries.iter().filter(|e| e.kind == EntryKind::File).count() as u64
+    }
+
+    fn bytes(&self) -> u64 {
+        self.entries.iter().map(Entry::size).sum()
+    }
+}
+
+/// Pack `paths` into a new archive at `destination` and read it back. With
+/// `remove_originals`, each item is then deleted unless it changed since it
+/// was listed. A failed or cancelled archive is deleted and nothing is
+/// removed.
+pub fn archive_items(
+    paths: &[String],
+    destination: &Path,
+    format: ArchiveFormat,
+    remove_originals: bool,
+    progress: &mut Progress,
+) -> Result<FileOpResult, String> {
+    let items = list_items(paths, destination, progress)?;
+    let files: u64 = items.iter().map(Item::files).sum();
+    let bytes: u64 = items.iter().map(Item::bytes).sum();
+
+    let file = OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(destination)
+        .map_err(|e| format!("Cannot create {}: {}", destination.display(), e))?;
+    let written = write_archive(file, format, &items, progress)
+        .and_then(|_| verify_archive(destination, format, &items, progress));
+    if let Err(e) = written {
+        let _ = fs::remove_file(destination);
+        return Err(e);
+    }
+
+    let archive = destination.to_string_lossy().to_string();
+    let mut results = Vec::with_capacity(items.len());
+    progress.phase(FileOpPhase::Removing, items.len() as u64, 0);
+    for item in &items {
+        let mut result = FileOpItem {
+            source: item.source.to_string_lossy().to_string(),
+            destination: Some(archive.clone()),
+            removed: false,
+            error: None,
+        };
+        if remove_originals {
+            match remove_original(item, progress) {
+                Ok(()) => result.removed = true,
+                Err(e) => result.error = Some(e),
+            }
+        }
+        results.push(result);
+    }
+
+    Ok(FileOpResult {
+        op_id: progress.state.op_id.clone(),
+        destination: archive,
+        items: results,
+        files,
+        bytes,
+        archive_size: fs::metadata(destination).map(|m| m.len()).ok(),
+    })
+}
+
+/// Move `paths` into the existing directory `destination`. Items on the same
+/// filesystem are renamed; others are copied, verified by checksum and then
+/// removed. With `keep_originals` every item is copied and kept.
+pub fn move_items(
+    paths: &[String],
+    destination: &Path,
+    keep_originals: bool,
+    progress: &mut Progress,
+) -> Result<FileOpResult, String> {
+    if !destination.is_dir() {
+        return Err(format!("{} is not a directory", destination.display()));
+    }
+    let items = list_items(paths, destination, progress)?;
+    let files: u64 = items.iter().map(Item::files).sum();
+    let bytes: u64 = items.iter().map(Item::bytes).sum();
+
+    let mut results = Vec::with_capacity(items.len());
+    progress.phase(FileOpPhase::Copying, files, bytes);
+    for item in &items {
+        let target = destination.join(&item.name);
+        let mut result = FileOpItem {
+            source: item.source.to_string_lossy().to_string(),
+            destination: None,
+            removed: false,
+            error: None,
+        };
+        match move_item(item, &target, keep_originals, progress) {
+            Ok(removed) => {
+                result.destination = Some(target.to_string_lossy().to_string());
+                result.removed = removed;
+            }
+            Err(e) => result.error = Some(e),
+        }
+        results.push(result);
+    }
+
+    Ok(FileOpResult {
+        op_id: progress.state.op_id.clone(),
+        destination: destination.to_string_lossy().to_string(),
+        items: results,
+        files,
+        bytes,
+        archive_size: None,
+    })
+}
+
+// Lists every selected item, refusing selections that would clash in the
+// destination or contain it.
+fn list_items(paths: &[String], destination: &Path, progress: &mut Progress) -> Result<Vec<Item>, String> {
+    if paths.is_empty() {
+        return Err("Nothing selected".to_string());
+    }
+    let destination = resolve(destination);
+    let mut items: Vec<Item> = Vec::with_capacity(paths.len());
+    progress.phase(FileOpPhase::Measuring, 0, 0);
+    for path in paths {
+        let source = PathBuf::from(path);
+        fs::symlink_metadata(&source).map_err(|e| format!("{}: {}", path, e))?;
+        let name = source
+            .file_name()
+            .and_then(|n| n.to_str())
+            .ok_or_else(|| format!("{}: name is not valid UTF-8", path))?
+            .to_string();
+        if items.iter().any(|i| i.name == name) {
+            return Err(format!("More than one selected item is named {}", name));
+        }
+        if destination.starts_with(resolve(&source)) {
+            return Err(format!("The destination is inside {}", path));
+        }
+        let item = Item { entries: walk_item(&source, &name, progress)?, source, name };
+        progress.add_bytes(item.bytes());
+        progress.state.files_done += item.files();
+        items.push(item);
+    }
+    Ok(items)
+}
+
+fn walk_item(source: &Path, name: &str, progress: &mut Progress) -> Result<Vec<Entry>, String> {
+    let mut entries = Vec::new();
+    let walker = walkdir::WalkDir::new(source).follow_links(false).sort_by_file_name();
+    for entry in walker {
+        let entry = entry.map_err(|e| e.to_string())?;
+        progress.start(entry.path())?;
+        let mut entry_name = name.to_string();
+        for part in entry.path().strip_prefix(source).unwrap_or(Path::new("")).components() {
+            if let Component::Normal(part) = part {
+                let part = part
+                    .to_str()
+                    .ok_or_else(|| format!("{}: name is not valid UTF-8", entry.path().display()))?;
+                entry_name.push('/');
+                entry_name.push_str(part);
+            }
+        }
+        let meta = entry.metadata().map_err(|e| format!("{}: {}", entry.path().display(), e))?;
+        let kind = if meta.file_type().is_symlink() {
+            EntryKind::Symlink
+        } else if meta.is_dir() {
+            EntryKind::Dir
+        } else if meta.is_file() {
+            EntryKind::File
+        } else {
+            // Sockets, fifos and device nodes can't be archived or copied
+            // faithfully; skipping them would lose them on removal.
+            return Err(format!("{}: not a regular file or directory", entry.path().display()));
+        };
+        entries.push(Entry { path: entry.into_path(), name: entry_name, kind, meta });
+    }
+    Ok(entries)
+}
+
+// Canonical form of a path that may not exist yet: its deepest existing
+// ancestor canonicalised, with the rest appended.
+fn resolve(path: &Path) -> PathBuf {
+    let mut existing = path;
+    let mut rest = Vec::new();
+    loop {
+        if let Ok(canonical) = existing.canonicalize() {
+            return rest.iter().rev().fold(canonical, |acc, part| acc.join(part));
+        }
+        match (existing.parent(), existing.file_name()) {
+            (Some(parent), Some(name)) => {
+                rest.push(name.to_os_string());
+                existing = parent;
+            }
+            _ => return path.to_path_buf(),
+        }
+    }
+}
+
+fn write_archive(file: File, format: ArchiveFormat, items: &[Item], progress: &mut Progress) -> Result<(), String> {
+    let files: u64 = items.iter().map(Item::files).sum();
+    let bytes: u64 = items.iter().map(Item::bytes).sum();
+    progress.phase(FileOpPhase::Archiving, files, bytes);
+    let out = BufWriter::new(file);
+    let out = match format {
+        ArchiveFormat::TarGz => write_tar_gz(out, items, progress)?,
+        ArchiveFormat::Zip => write_zip(out, items, progress)?,
+    };
+    let file = out.into_inner().map_err(|e| e.to_string())?;
+    file.sync_all().map_err(|e| e.to_string())
+}
+
+fn write_tar_gz(
+    out: BufWriter<File>,
+    items: &[Item],
+    progress: &mut Progress,
+) -> Result<BufWriter<File>, String> {
+    let encoder = flate2::write::GzEncoder::new(out, flate2::Compression::default());
+    let mut builder = tar::Builder::new(encoder);
+    builder.follow_symlinks(false);
+    for entry in items.iter().flat_map(|i| &i.entries) {
+        progress.start(&entry.path)?;
+        let appended = match entry.kind {
+            EntryKind::Dir | EntryKind::Symlink => builder.append_path_with_name(&entry.path, &entry.name),
+            EntryKind::File => {
+                let mut header = tar::Header::new_gnu();
+                header.set_metadata(&entry.meta);
+                let file = File::open(&entry.path).map_err(|e| format!("{}: {}", entry.path.display(), e))?;
+                let mut reader = ProgressReader::new(file.take(entry.meta.len()), progress);
+                builder
+                    .append_data(&mut header, &entry.name, &mut reader)
+                    .and_then(|_| check_length(entry, reader.read))
+            }
+        };
+        appended.map_err(|e| format!("{}: {}", entry.path.display(), e))?;
+        if entry.kind == EntryKind::File {
+            progress.file_done();
+        }
+    }
+    let encoder = builder.into_inner().map_err(|e| e.to_string())?;
+    encoder.finish().map_err(|e| e.to_string())
+}
+
+fn write_zip(out: BufWriter<File>, items: &[Item], progress: &mut Progress) -> Result<BufWriter<File>, String> {
+    use zip::write::SimpleFileOptions;
+
+    let mut zip = zip::ZipWriter::new(out);
+    for entry in items.iter().flat_map(|i| &i.entries) {
+        progress.start(&entry.path)?;
+        let mut options = SimpleFileOptions::default()
+            .compression_method(zip::CompressionMethod::Deflated)
+            .last_modified_time(zip_time(&entry.meta));
+        if let (_, _, Some(mode)) = ownership_of(&entry.meta) {
+            options = options.unix_permissions(mode);
+        }
+        let context = |e: &dyn std::fmt::Display| format!("{}: {}", entry.path.display(), e);
+        match entry.kind {
+            EntryKind::Dir => zip.add_directory(format!("{}/", entry.name), options).map_err(|e| context(&e))?,
+            EntryKind::Symlink => {
+                let target = fs::read_link(&entry.path).map_err(|e| context(&e))?;
+                zip.add_symlink(&entry.name, target.to_string_lossy(), options).map_err(|e| context(&e))?;
+            }
+            EntryKind::File => {
+                let size = entry.meta.len();
+                zip.start_file(&entry.name, options.large_file(size >= u32::MAX as u64))
+                    .map_err(|e| context(&e))?;
+                let file = File::open(&entry.path).map_err(|e| context(&e))?;
+                let mut reader = ProgressReader::new(file.take(size), progress);
+                io::copy(&mut reader, &mut zip)
+                    .and_then(|_| check_length(entry, reader.read))
+                    .map_err(|e| context(&e))?;
+                progress.file_done();
+            }
+        }
+    }
+    zip.finish().map_err(|e| e.to_string())
+}
+
+// A file that shrank while being read would leave a short, corrupt entry.
+fn check_length(entry: &Entry, read: u64) -> io::Result<()> {
+    if read == entry.meta.len() {
+        Ok(())
+    } else {
+        Err(io::Error::new(io::ErrorKind::UnexpectedEof, "file changed while being read"))
+    }
+}
+
+// Zip stores local time with two-second resolution from 1980 on.
+fn zip_time(meta: &Metadata) -> zip::DateTime {
+    let Ok(modified) = meta.modified() else {
+        return zip::DateTime::default();
+    };
+    let local: chrono::DateTime<chrono::Local> = modified.into();
+    zip::DateTime::from_date_and_time(
+        u16::try_from(local.year()).unwrap_or(0),
+        local.month() as u8,
+        local.day() as u8,
+        local.hour() as u8,
+        local.minute() as u8,
+        local.second() as u8,
+    )
+    .unwrap_or_default()
+}
+
+// Reads every entry back (which checks the gzip or zip CRCs) and compares
+// the archive's listing with the one it was written from.
+fn verify_archive(path: &Path, format: ArchiveFormat, items: &[Item], progress: &mut Progress) -> Result<(), String> {
+    let mut expected: HashMap<&str, (EntryKind, u64)> = items
+        .iter()
+        .flat_map(|i| &i.entries)
+        .map(|e| (e.name.as_str(), (e.kind, e.size())))
+        .collect();
+    let files: u64 = items.iter().map(Item::files).sum();
+    let bytes: u64 = items.iter().map(Item::bytes).sum();
+    progress.phase(FileOpPhase::Verifying, files, bytes);
+
+    let mut check = |name: &str, kind: EntryKind, reader: &mut dyn Read, progress: &mut Progress| {
+        let name = name.trim_end_matches('/');
+        let (expected_kind, size) = expected
+            .remove(name)
+            .ok_or_else(|| format!("Unexpected entry {} in archive", name))?;
+        if kind != expected_kind {
+            return Err(format!("{} has the wrong type in the archive", name));
+        }
+        if kind == EntryKind::File {
+            let mut reader = ProgressReader::new(reader, progress);
+            let read = io::copy(&mut reader, &mut io::sink()).map_err(|e| format!("{}: {}", name, e))?;
+            if read != size {
+                return Err(format!("{} is {} bytes in the archive, expected {}", name, read, size));
+            }
+            progress.file_done();
+        }
+        Ok(())
+    };
+
+    let file = File::open(path).map_err(|e| e.to_string())?;
+    match format {
+        ArchiveFormat::TarGz => {
+            let mut archive = tar::Archive::new(flate2::read::GzDecoder::new(io::BufReader::new(file)));
+            for entry in archive.entries().map_err(|e| e.to_string())? {
+                let mut entry = entry.map_err(|e| e.to_string())?;
+                let name = entry.path().map_err(|e| e.to_string())?.to_string_lossy().to_string();
+                let kind = match entry.header().entry_type() {
+                    tar::EntryType::Directory => EntryKind::Dir,
+                    tar::EntryType::Symlink => EntryKind::Symlink,
+                    _ => EntryKind::File,
+                };
+                check(&name, kind, &mut entry, progress)?;
+            }
+        }
+        ArchiveFormat::Zip => {
+            let mut archive = zip::ZipArchive::new(io::BufReader::new(file)).map_err(|e| e.to_string())?;
+            for index in 0..archive.len() {
+                let mut entry = archive.by_index(index).map_err(|e| e.to_string())?;
+                let name = entry.name().to_string();
+                let kind = if entry.is_dir() {
+                    EntryKind::Dir
+                } else if entry.is_symlink() {
+                    EntryKind::Symlink
+                } else {
+                    EntryKind::File
+                };
+                check(&name, kind, &mut entry, progress)?;
+            }
+        }
+    }
+    match expected.keys().next() {
+        Some(missing) => Err(format!("{} is missing from the archive", missing)),
+        None => Ok(()),
+    }
+}
+
+// Renames, or copies and verifies, one item to `target`. Returns whether the
+// original is gone.
+fn move_item(item: &Item, target: &Path, keep_original: bool, progress: &mut Progress) -> Result<bool, String> {
+    progress.start(&item.source)?;
+    if fs::symlink_metadata(target).is_ok() {
+        return Err(format!("{} already exists", target.display()));
+    }
+    if !keep_original {
+        match fs::rename(&item.source, target) {
+            Ok(()) => {
+                progress.add_bytes(item.bytes());
+                progress.state.files_done += item.files();
+                return Ok(true);
+            }
+            Err(e) if e.raw_os_error() == Some(CROSS_DEVICE) => {}
+            Err(e) => return Err(e.to_string()),
+        }
+    }
+
+    let mut created = false;
+    let copied = copy_item(item, target, progress, &mut created);
+    if let Err(e) = copied {
+        // Only ever remove what this operation created: `target` may have
+        // appeared since the check above, and then it isn't ours.
+        if !created {
+            return Err(e);
+        }
+        let _ = if item.entries[0].kind == EntryKind::Dir {
+            fs::remove_dir_all(target)
+        } else {
+            fs::remove_file(target)
+        };
+        return Err(e);
+    }
+    if keep_original {
+        return Ok(false);
+    }
+    remove_original(item, progress)?;
+    Ok(true)
+}
+
+// Copies and verifies one item. `created` is set once `target` itself has
+// been created by this copy, so a failed copy knows whether it may clean up.
+fn copy_item(item: &Item, target: &Path, progress: &mut Progress, created: &mut bool) -> Result<(), String> {
+    let mut hashes: HashMap<&str, [u8; 32]> = HashMap::new();
+    for entry in &item.entries {
+        progress.start(&entry.path)?;
+        let dest = target_path(target, &entry.name);
+        let is_target = dest == target;
+        let context = |e: io::Error| format!("{}: {}", dest.display(), e);
+        match entry.kind {
+            EntryKind::Dir => fs::create_dir(&dest).map_err(context)?,
+            EntryKind::Symlink => copy_symlink(&entry.path, &dest).map_err(context)?,
+            EntryKind::File => {
+                let out = OpenOptions::new().write(true).create_new(true).open(&dest).map_err(context)?;
+                *created |= is_target;
+                let hash = copy_file(entry, out, progress).map_err(context)?;
+                hashes.insert(&entry.name, hash);
+                progress.file_done();
+            }
+        }
+        *created |= is_target;
+    }
+    // Directory times last, once nothing more is written into them.
+    for entry in item.entries.iter().rev().filter(|e| e.kind == EntryKind::Dir) {
+        let dest = target_path(target, &entry.name);
+        let _ = fs::set_permissions(&dest, entry.meta.permissions());
+        if let Ok(modified) = entry.meta.modified() {
+            // Best effort: not every platform can open a directory for this.
+            let _ = File::open(&dest).and_then(|f| f.set_modified(modified));
+        }
+    }
+
+    let copying = progress.state.clone();
+    progress.phase(FileOpPhase::Verifying, item.files(), item.bytes());
+    for entry in item.entries.iter().filter(|e| e.kind == EntryKind::File) {
+        let dest = target_path(target, &entry.name);
+        progress.start(&dest)?;
+        let file = File::open(&dest).map_err(|e| format!("{}: {}", dest.display(), e))?;
+        let hash = hash_reader(ProgressReader::new(file, progress)).map_err(|e| format!("{}: {}", dest.display(), e))?;
+        if hashes.get(entry.name.as_str()) != Some(&hash) {
+            return Err(format!("{} does not match the original after copying", dest.display()));
+        }
+        progress.file_done();
+    }
+    progress.state = copying;
+    progress.emit();
+    Ok(())
+}
+
+fn target_path(target: &Path, name: &str) -> PathBuf {
+    name.split('/').skip(1).fold(target.to_path_buf(), |acc, part| acc.join(part))
+}
+
+// Copies one file into the newly created `out`, returning the SHA-256 of
+// what was read from the source.
+fn copy_file(entry: &Entry, mut out: File, progress: &mut Progress) -> io::Result<[u8; 32]> {
+    let mut reader = ProgressReader::new(File::open(&entry.path)?.take(entry.meta.len()), progress);
+    let mut hasher = Sha256::new();
+    let mut buf = vec![0u8; COPY_BUFFER];
+    loop {
+        let n = reader.read(&mut buf)?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buf[..n]);
+        out.write_all(&buf[..n])?;
+    }
+    check_length(entry, reader.read)?;
+    out.set_permissions(entry.meta.permissions())?;
+    if let Ok(modified) = entry.meta.modified() {
+        out.set_modified(modified)?;
+    }
+    out.sync_all()?;
+    Ok(hasher.finalize().into())
+}
+
+fn hash_reader(mut reader: impl Read) -> io::Result<[u8; 32]> {
+    let mut hasher = Sha256::new();
+    let mut buf = vec![0u8; COPY_BUFFER];
+    loop {
+        let n = reader.read(&mut buf)?;
+        if n == 0 {
+            return Ok(hasher.finalize().into());
+        }
+        hasher.update(&buf[..n]);
+    }
+}
+
+#[cfg(unix)]
+fn copy_symlink(source: &Path, dest: &Path) -> io::Result<()> {
+    std::os::unix::fs::symlink(fs::read_link(source)?, dest)
+}
+
+#[cfg(not(unix))]
+fn copy_symlink(_source: &Path, _dest: &Path) -> io::Result<()> {
+    Err(io::Error::new(io::ErrorKind::Unsupported, "symbolic links can't be copied on this platform"))
+}
+
+// Deletes the original of an item that has been safely copied, after
+// checking that nothing in it changed since it was listed.
+fn remove_original(item: &Item, progress: &mut Progress) -> Result<(), String> {
+    progress.start(&item.source)?;
+    let before: Vec<_> = item.entries.iter().map(Entry::signature).collect();
+    let current = walk_item(&item.source, &item.name, progress)?;
+    let after: Vec<_> = current.iter().map(Entry::signature).collect();
+    if before != after {
+        return Err(format!("{} changed during the operation; the original was kept", item.source.display()));
+    }
+    let removed = if item.entries[0].kind == EntryKind::Dir {
+        fs::remove_dir_all(&item.source)
+    } else {
+        fs::remove_file(&item.source)
+    };
+    removed.map_err(|e| format!("{}: {}", item.source.display(), e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TempDir;
+
+    fn fixture(name: &str) -> TempDir {
+        let root = TempDir::new(&format!("fileops-{}", name));
+        fs::create_dir_all(root.join("project/src")).unwrap();
+        fs::write(root.join("project/src/main.rs"), b"fn main() {}\n").unwrap();
+        fs::write(root.join("project/data.bin"), vec![7u8; 300_000]).unwrap();
+        fs::write(root.join("notes.txt"), b"notes").unwrap();
+        #[cfg(unix)]
+        std::os::unix::fs::symlink("data.bin", root.join("project/link")).unwrap();
+        root
+    }
+
+    fn sources(root: &Path) -> Vec<String> {
+        vec![
+            root.join("project").to_string_lossy().to_string(),
+            root.join("notes.txt").to_string_lossy().to_string(),
+        ]
+    }
+
+    #[test]
+    fn archives_verify_and_remove_originals() {
+        for format in [ArchiveFormat::TarGz, ArchiveFormat::Zip] {
+            let root = fixture(if format == ArchiveFormat::Zip { "zip" } else { "tgz" });
+            let cancel = AtomicBool::new(false);
+            let phases = std::cell::RefCell::new(Vec::new());
+            let report = |p: &FileOpProgress| phases.borrow_mut().push(p.phase);
+            let mut progress = Progress::new("op", &cancel, &report);
+
+            let dest = root.join("out.archive");
+            let result = archive_items(&sources(&root), &dest, format, true, &mut progress).unwrap();
+            assert_eq!(result.files, 3);
+            assert_eq!(result.bytes, 300_018);
+            assert!(result.items.iter().all(|i| i.removed && i.error.is_none()));
+            assert!(!root.join("project").exists());
+            assert!(phases.borrow().contains(&FileOpPhase::Verifying));
+
+            // The archive can't contain its own destination.
+            let inside = root.join("nested");
+            fs::create_dir(&inside).unwrap();
+            let err = archive_items(
+                &[inside.to_string_lossy().to_string()],
+                &inside.join("x.zip"),
+                format,
+                false,
+                &mut progress,
+            )
+            .unwrap_err();
+            assert!(err.contains("inside"));
+        }
+    }
+
+    #[test]
+    fn cancelling_mid_file_stops_and_deletes_the_archive() {
+        for format in [ArchiveFormat::TarGz, ArchiveFormat::Zip] {
+            for phase in [FileOpPhase::Archiving, FileOpPhase::Verifying] {
+                let root = fixture(&format!("cancel-{:?}-{:?}", format, phase));
+                let cancel = AtomicBool::new(false);
+                // Cancel once the phase has read some bytes, i.e. inside a
+                // file rather than between entries.
+                let report = |p: &FileOpProgress| {
+                    if p.phase == phase && p.bytes_done > 0 {
+                        cancel.store(true, Ordering::Relaxed);
+                    }
+                };
+                let mut progress = Progress::new("op", &cancel, &report);
+                progress.interval = Duration::ZERO;
+
+                let dest = root.join("out.archive");
+                let err = archive_items(&sources(&root), &dest, format, true, &mut progress).unwrap_err();
+                assert!(err.contains("Cancelled"), "{}", err);
+                assert!(!dest.exists());
+                assert!(root.join("project/data.bin").exists());
+            }
+        }
+    }
+
+    #[test]
+    fn copy_keeps_originals_and_refuses_to_overwrite() {
+        let root = fixture("copy");
+        let dest = root.join("dest");
+        fs::create_dir(&dest).unwrap();
+        let cancel = AtomicBool::new(false);
+        let report = |_: &FileOpProgress| {};
+        let mut progress = Progress::new("op", &cancel, &report);
+
+        let result = move_items(&sources(&root), &dest, true, &mut progress).unwrap();
+        assert!(result.items.iter().all(|i| !i.removed && i.error.is_none()));
+        assert_eq!(fs::read(dest.join("project/data.bin")).unwrap(), vec![7u8; 300_000]);
+        assert_eq!(fs::read(dest.join("notes.txt")).unwrap(), b"notes");
+        #[cfg(unix)]
+        assert_eq!(fs::read_link(dest.join("project/link")).unwrap(), Path::new("data.bin"));
+        assert!(root.join("project/data.bin").exists());
+
+        let result = move_items(&sources(&root), &dest, false, &mut progress).unwrap();
+        assert!(result.items.iter().all(|i| i.error.as_deref().is_some_and(|e| e.contains("already exists"))));
+
+        // Same filesystem: a rename.
+        let other = root.join("other");
+        fs::create_dir(&other).unwrap();
+        let result = move_items(&sources(&root), &other, false, &mut progress).unwrap();
+        assert!(result.items.iter().all(|i| i.removed));
+        assert!(other.join("project/src/main.rs").exists());
+        assert!(!root.join("project").exists());
+    }
+
+    #[test]
+    fn failed_copy_leaves_a_target_it_did_not_create() {
+        let root = fixture("race");
+        let cancel = AtomicBool::new(false);
+        let report = |_: &FileOpProgress| {};
+        let mut progress = Progress::new("op", &cancel, &report);
+        let source = root.join("project");
+        let item = Item {
+            entries: walk_item(&source, "project", &mut progress).unwrap(),
+            source,
+            name: "project".to_string(),
+        };
+
+        // The target appeared after move_item checked for it.
+        let target = root.join("theirs");
+        fs::create_dir(&target).unwrap();
+        fs::write(target.join("keep.txt"), b"mine").unwrap();
+        let mut created = false;
+        assert!(copy_item(&item, &target, &mut progress, &mut created).is_err());
+        assert!(!created);
+        assert!(target.join("keep.txt").exists());
+    }
+}
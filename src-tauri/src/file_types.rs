@@ -0,0 +1,134 @@
+// File-type breakdown for scanned trees.
+//
+// Aggregates bytes and file counts per lowercase extension and per coarse
+// category (video, archives, VM images, ...) so the storage analyzer and the
+// AI context can answer "what kind of data is eating this disk". Computed
+// by the scanner when ScanOptions::file_types is set.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::Path;
+
+/// Key used for files without an extension.
+pub const NO_EXTENSION: &str = "(none)";
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+pub struct TypeStat {
+    pub bytes: u64,
+    pub count: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+pub struct FileTypeBreakdown {
+    pub by_extension: HashMap<String, TypeStat>,
+    pub by_category: HashMap<String, TypeStat>,
+}
+
+impl FileTypeBreakdown {
+    pub fn add_file(&mut self, path: &Path, size: u64) {
+        let ext = extension_key(path);
+        let category = category_for_extension(&ext);
+        add(self.by_extension.entry(ext).or_default(), size, 1);
+        add(self.by_category.entry(category.to_string()).or_default(), size, 1);
+    }
+
+    pub fn merge(&mut self, other: &FileTypeBreakdown) {
+        for (ext, stat) in &other.by_extension {
+            add(self.by_extension.entry(ext.clone()).or_default(), stat.bytes, stat.count);
+        }
+        for (cat, stat) in &other.by_category {
+            add(self.by_category.entry(cat.clone()).or_default(), stat.bytes, stat.count);
+        }
+    }
+
+    /// Inverse of `merge`; entries that drop to zero files are removed.
+    pub fn subtract(&mut self, other: &FileTypeBreakdown) {
+        subtract_map(&mut self.by_extension, &other.by_extension);
+        subtract_map(&mut self.by_category, &other.by_category);
+    }
+
+    /// Bytes per category, the shape `ai::ScanSummary::file_types` expects.
+    pub fn bytes_by_category(&self) -> HashMap<String, u64> {
+        self.by_category
+            .iter()
+            .map(|(cat, stat)| (cat.clone(), stat.bytes))
+            .collect()
+    }
+}
+
+fn add(stat: &mut TypeStat, bytes: u64, count: u64) {
+    stat.bytes += bytes;
+    stat.count += count;
+}
+
+fn subtract_map(target: &mut HashMap<String, TypeStat>, other: &HashMap<String, TypeStat>) {
+    for (key, stat) in other {
+        if let Some(existing) = target.get_mut(key) {
+            existing.bytes = existing.bytes.saturating_sub(stat.bytes);
+            existing.count = existing.count.saturating_sub(stat.count);
+            if existing.count == 0 {
+                target.remove(key);
+            }
+        }
+    }
+}
+
+pub fn extension_key(path: &Path) -> String {
+    path.extension()
+        .map(|e| e.to_string_lossy().to_lowercase())
+        .filter(|e| !e.is_empty())
+        .unwrap_or_else(|| NO_EXTENSION.to_string())
+}
+
+pub fn category_for_extension(ext: &str) -> &'static str {
+    match ext {
+        "mp4" | "mkv" | "mov" | "avi" | "wmv" | "webm" | "m4v" | "flv" | "mpg" | "mpeg" | "m2ts" | "mts" => "video",
+        "mp3" | "flac" | "wav" | "aac" | "ogg" | "m4a" | "opus" | "wma" => "audio",
+        "jpg" | "jpeg" | "png" | "gif" | "bmp" | "tif" | "tiff" | "webp" | "heic" | "raw" | "cr2" | "nef" | "psd" | "svg" => "images",
+        "zip" | "tar" | "gz" | "tgz" | "bz2" | "xz" | "zst" | "7z" | "rar" | "lz4" | "cab" => "archives",
+        "vmdk" | "vdi" | "vhd" | "vhdx" | "qcow2" | "qcow" | "ova" | "ovf" | "hdd" => "vm_images",
+        "iso" | "img" | "dmg" | "wim" => "disk_images",
+        "pdf" | "doc" | "docx" | "xls" | "xlsx" | "ppt" | "pptx" | "odt" | "ods" | "odp" | "txt" | "md" | "rtf" | "csv" | "epub" => "documents",
+        "rs" | "c" | "h" | "cpp" | "hpp" | "cc" | "cs" | "java" | "kt" | "go" | "py" | "rb" | "js" | "jsx" | "ts" | "tsx"
+        | "php" | "swift" | "scala" | "sh" | "ps1" | "html" | "css" | "scss" | "vue" | "json" | "yaml" | "yml" | "toml" | "xml" | "sql" => "source",
+        "o" | "obj" | "a" | "lib" | "rlib" | "class" | "jar" | "pyc" | "wasm" | "pdb" => "build_artifacts",
+        "exe" | "dll" | "so" | "dylib" | "msi" | "deb" | "rpm" | "apk" | "appimage" | "pkg" => "executables",
+        "db" | "sqlite" | "sqlite3" | "mdb" | "accdb" | "ldf" | "mdf" | "ibd" => "databases",
+        "log" | "journal" => "logs",
+        _ => "other",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn categorises_case_insensitively() {
+        let mut b = FileTypeBreakdown::default();
+        b.add_file(Path::new("/data/Movie.MKV"), 100);
+        b.add_file(Path::new("/vms/disk.qcow2"), 50);
+        b.add_file(Path::new("/tmp/README"), 1);
+        assert_eq!(b.by_extension["mkv"], TypeStat { bytes: 100, count: 1 });
+        assert_eq!(b.by_category["video"].bytes, 100);
+        assert_eq!(b.by_category["vm_images"].bytes, 50);
+        assert_eq!(b.by_extension[NO_EXTENSION].count, 1);
+        assert_eq!(category_for_extension("ts"), "source");
+        assert_eq!(category_for_extension("m2ts"), "video");
+    }
+
+    #[test]
+    fn merge_then_subtract_round_trips() {
+        let mut a = FileTypeBreakdown::default();
+        a.add_file(Path::new("a.zip"), 10);
+        let mut b = FileTypeBreakdown::default();
+        b.add_file(Path::new("b.zip"), 5);
+        b.add_file(Path::new("c.png"), 7);
+
+        let original = a.clone();
+        a.merge(&b);
+        assert_eq!(a.by_category["archives"], TypeStat { bytes: 15, count: 2 });
+        a.subtract(&b);
+        assert_eq!(a, original);
+    }
+}
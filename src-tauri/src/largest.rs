@@ -0,0 +1,284 @@
+// Top-N largest files and directories anywhere under a path.
+//
+// The scanner only keeps per-entry detail down to the lookahead depth; below
+// that `get_deep_stats` collapses everything into a (size, count) aggregate,
+// so a 40 GB file six levels down is invisible in the tree. This does a single
+// walk instead, keeping only the N biggest files and directories in bounded
+// min-heaps so memory stays flat regardless of tree size.
+//
+// The walk honours the scan's exclusions, filesystem boundaries and symlink
+// policy, and counts a hard-linked file once, so /proc/kcore or a second
+// link to a big file never tops the list.
+
+use serde::{Deserialize, Serialize};
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::SystemTime;
+
+use crate::file_types::extension_key;
+use crate::scan_filter::ScanFilter;
+use crate::scanner::{modified_secs, HardLinks, ScanOptions, ScanStats, SymlinkPolicy};
+
+const DEFAULT_LIMIT: usize = 50;
+const MAX_LIMIT: usize = 10_000;
+
+/// Optional filters applied to files. Directory sizes only count the files
+/// that pass, so "largest folders of .log files older than 30 days" works.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct LargestFilter {
+    /// Only files last modified at least this many days ago.
+    pub min_age_days: Option<u64>,
+    /// Only files last modified at most this many days ago.
+    pub max_age_days: Option<u64>,
+    /// Lowercase extensions without the dot; "(none)" matches files without one.
+    pub extensions: Option<Vec<String>>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct LargestEntry {
+    pub path: String,
+    pub size: u64,
+    pub last_modified: u64,
+    /// Matching files inside (1 for files).
+    pub file_count: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct LargestReport {
+    pub root: String,
+    /// Largest first.
+    pub files: Vec<LargestEntry>,
+    /// Largest first; the root itself is not included.
+    pub directories: Vec<LargestEntry>,
+    pub files_scanned: u64,
+    pub files_matched: u64,
+}
+
+// Keeps the `limit` largest entries seen so far.
+struct TopN {
+    limit: usize,
+    heap: BinaryHeap<Reverse<(u64, u64, u64, PathBuf)>>,
+}
+
+impl TopN {
+    fn new(limit: usize) -> Self {
+        Self { limit, heap: BinaryHeap::with_capacity(limit + 1) }
+    }
+
+    fn push(&mut self, size: u64, last_modified: u64, file_count: u64, path: &Path) {
+        if self.heap.len() == self.limit {
+            match self.heap.peek() {
+                Some(Reverse((smallest, ..))) if size > *smallest => {
+                    self.heap.pop();
+                }
+                _ => return,
+            }
+        }
+        self.heap.push(Reverse((size, last_modified, file_count, path.to_path_buf())));
+    }
+
+    fn into_sorted(self) -> Vec<LargestEntry> {
+        // Ascending order of Reverse(..) is descending size.
+        self.heap
+            .into_sorted_vec()
+            .into_iter()
+            .map(|Reverse((size, last_modified, file_count, path))| LargestEntry {
+                path: path.to_string_lossy().to_string(),
+                size,
+                last_modified,
+                file_count,
+            })
+            .collect()
+    }
+}
+
+impl LargestFilter {
+    fn matches(&self, path: &Path, modified: u64, now: u64) -> bool {
+        let age_days = now.saturating_sub(modified) / 86_400;
+        if self.min_age_days.is_some_and(|min| age_days < min) {
+            return false;
+        }
+        if self.max_age_days.is_some_and(|max| age_days > max) {
+            return false;
+        }
+        if let Some(exts) = &self.extensions {
+            let ext = extension_key(path);
+            if !exts.iter().any(|e| e.trim_start_matches('.').eq_ignore_ascii_case(&ext)) {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+pub fn find_largest(
+    root: &str,
+    limit: Option<usize>,
+    filter: &LargestFilter,
+    scan: &ScanOptions,
+    stats: Option<Arc<ScanStats>>,
+    cancel: Option<Arc<AtomicBool>>,
+) -> Result<LargestReport, String> {
+    let root_path = Path::new(root);
+    if !root_path.is_dir() {
+        return Err("Directory does not exist".to_string());
+    }
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).clamp(1, MAX_LIMIT);
+    let now = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+
+    let mut files = TopN::new(limit);
+    let mut dirs = TopN::new(limit);
+    let mut files_scanned = 0u64;
+    let mut files_matched = 0u64;
+    let scan_filter = ScanFilter::new(root_path, scan)?;
+    let links = HardLinks::default();
+
+    // Pre-order, so excluded directories are pruned before they're entered
+    // (walkdir can't skip a directory it yields after its contents). `open`
+    // holds the directories still being walked, outermost first, with their
+    // mtime and (size, count) so far: an entry at depth d finishes every
+    // open directory at depth >= d.
+    let mut open: Vec<(PathBuf, u64, u64, u64)> = vec![(root_path.to_path_buf(), 0, 0, 0)];
+    let mut close = |open: &mut Vec<(PathBuf, u64, u64, u64)>| {
+        let Some((path, modified, size, count)) = open.pop() else {
+            return;
+        };
+        // The root itself is not reported.
+        let Some(parent) = open.last_mut() else {
+            return;
+        };
+        parent.2 += size;
+        parent.3 += count;
+        if count > 0 {
+            dirs.push(size, modified, count, &path);
+        }
+    };
+
+    let walker = walkdir::WalkDir::new(root_path)
+        .min_depth(1)
+        .follow_links(scan.symlinks == SymlinkPolicy::Follow)
+        .into_iter()
+        .filter_entry(|e| e.metadata().map(|m| scan_filter.allows(e.path(), &m)).unwrap_or(true));
+    for (idx, entry) in walker.enumerate() {
+        if idx % 100 == 0 && cancel.as_ref().map(|c| c.load(Ordering::Relaxed)).unwrap_or(false) {
+            return Err("Cancelled".to_string());
+        }
+        let entry = match entry {
+            Ok(e) => e,
+            Err(_) => {
+                if let Some(st) = &stats {
+                    st.errors.fetch_add(1, Ordering::Relaxed);
+                }
+                continue;
+            }
+        };
+        while open.len() > entry.depth() {
+            close(&mut open);
+        }
+
+        if entry.file_type().is_dir() {
+            let modified = entry.metadata().map(|m| modified_secs(&m)).unwrap_or(0);
+            open.push((entry.into_path(), modified, 0, 0));
+            continue;
+        }
+        if !entry.file_type().is_file() {
+            continue;
+        }
+        let meta = match entry.metadata() {
+            Ok(m) if links.first_sighting(&m) => m,
+            _ => continue,
+        };
+        files_scanned += 1;
+        if let Some(st) = &stats {
+            st.scanned_files.fetch_add(1, Ordering::Relaxed);
+            st.total_size.fetch_add(meta.len(), Ordering::Relaxed);
+            if files_scanned % 64 == 1 {
+                if let Some(parent) = entry.path().parent() {
+                    st.set_current_dir(parent);
+                }
+            }
+        }
+
+        let modified = modified_secs(&meta);
+        if !filter.matches(entry.path(), modified, now) {
+            continue;
+        }
+        files_matched += 1;
+        if let Some(parent) = open.last_mut() {
+            parent.2 += meta.len();
+            parent.3 += 1;
+        }
+        files.push(meta.len(), modified, 1, entry.path());
+    }
+    while !open.is_empty() {
+        close(&mut open);
+    }
+
+    Ok(LargestReport {
+        root: root.to_string(),
+        files: files.into_sorted(),
+        directories: dirs.into_sorted(),
+        files_scanned,
+        files_matched,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TempDir;
+
+    #[test]
+    fn keeps_largest_files_and_dirs_with_extension_filter() {
+        let root = TempDir::new("largest");
+        std::fs::create_dir_all(root.join("a/deep/er")).unwrap();
+        std::fs::create_dir_all(root.join("b")).unwrap();
+        std::fs::write(root.join("a/deep/er/big.log"), vec![0u8; 5000]).unwrap();
+        std::fs::write(root.join("a/small.log"), vec![0u8; 10]).unwrap();
+        std::fs::write(root.join("b/mid.log"), vec![0u8; 300]).unwrap();
+        std::fs::write(root.join("b/huge.iso"), vec![0u8; 9000]).unwrap();
+
+        let filter = LargestFilter {
+            extensions: Some(vec![".LOG".to_string()]),
+            ..Default::default()
+        };
+        let scan = ScanOptions::default();
+        let report = find_largest(root.to_str().unwrap(), Some(2), &filter, &scan, None, None).unwrap();
+
+        assert_eq!(report.files_scanned, 4);
+        assert_eq!(report.files_matched, 3);
+        let sizes: Vec<u64> = report.files.iter().map(|f| f.size).collect();
+        assert_eq!(sizes, vec![5000, 300]);
+        assert!(report.files[0].path.ends_with("big.log"));
+
+        // a = 5010, a/deep = a/deep/er = 5000, b = 300 (the .iso is filtered out).
+        assert_eq!(report.directories.len(), 2);
+        assert_eq!(report.directories[0].size, 5010);
+        assert_eq!(report.directories[0].file_count, 2);
+        assert_eq!(report.directories[1].size, 5000);
+
+        // Exclusions apply and a second hard link adds nothing.
+        #[cfg(unix)]
+        {
+            std::fs::hard_link(root.join("a/deep/er/big.log"), root.join("b/big-link.log")).unwrap();
+            let scan = ScanOptions { exclude: vec!["small.log".to_string()], ..Default::default() };
+            let report = find_largest(root.to_str().unwrap(), None, &filter, &scan, None, None).unwrap();
+            assert_eq!(report.files_scanned, 3);
+            let sizes: Vec<u64> = report.files.iter().map(|f| f.size).collect();
+            assert_eq!(sizes, vec![5000, 300]);
+            // Whichever link the walk meets first carries the bytes.
+            let top: u64 = report.directories.iter()
+                .filter(|d| Path::new(&d.path).parent() == Some(&*root))
+                .map(|d| d.size)
+                .sum();
+            assert_eq!(top, 5300);
+        }
+    }
+}
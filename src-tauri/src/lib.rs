@@ -1,8 +1,23 @@
 mod scanner;
+mod scan_index;
+mod scan_watcher;
+mod duplicates;
+mod file_types;
+mod largest;
+mod owners;
+mod age_report;
+mod mounts;
+mod scan_filter;
+mod snapshots;
+mod scan_export;
 mod commands;
 mod ai;
 mod ai_commands;
 mod cleaner;
+mod cleaning_rules;
+mod dev_caches;
+mod log_cleanup;
+mod tool_cleanup;
 mod execute_command;
 mod shell_classify;
 mod system_tools;
@@ -13,6 +28,8 @@ mod skills;
 mod user_info;
 mod user_profile;
 mod audit_log;
+mod trash;
+mod file_ops;
 mod browser_capability;
 mod browser_classify;
 mod browser_commands;
@@ -25,6 +42,8 @@ mod perception_commands;
 mod mcp_types;
 mod mcp_client;
 pub mod mcp_server;
+#[cfg(test)]
+mod test_support;
 
 use std::str::FromStr;
 use tauri::Manager;
@@ -46,6 +65,9 @@ pub fn run() {
       if let Err(e) = workflow_recorder::seed_default_workflows(&app.handle()) {
         log::warn!("Failed to seed default workflows: {}", e);
       }
+      // Warm the storage analyzer cache from the persistent scan index
+      tauri::async_runtime::spawn_blocking(commands::load_scan_index);
+
       // Initialize SQLite-backed workflow run state
       match workflow_db::WorkflowDb::new() {
         Ok(db) => {
@@ -152,12 +174,34 @@ pub fn run() {
     .invoke_handler(tauri::generate_handler![
         commands::scan_dir,
         commands::refresh_scan,
+        commands::refresh_scan_incremental,
+        commands::expand_node,
+        commands::get_scan_summary,
         commands::clear_cache,
         commands::reveal_in_explorer,
         commands::open_file,
         commands::delete_item,
+        commands::trash_supported,
+        commands::list_trashed_items,
+        commands::restore_trashed_item,
+        commands::archive_items,
+        commands::move_items,
+        commands::cancel_file_operation,
         commands::get_drives,
+        commands::find_duplicates,
+        commands::delete_duplicates,
+        commands::get_largest_items,
+        commands::get_owner_usage,
+        commands::get_age_report,
+        commands::save_scan_snapshot,
+        commands::list_scan_snapshots,
+        commands::delete_scan_snapshot,
+        commands::diff_scan_snapshots,
+        commands::export_scan,
         commands::cancel_scan,
+        commands::list_running_scans,
+        scan_watcher::watch_scan_tree,
+        scan_watcher::unwatch_scan_tree,
         ai_commands::get_ai_providers_status,
         ai_commands::get_provider_models,
         ai_commands::run_ai_inference,
@@ -165,10 +209,13 @@ pub fn run() {
         ai_commands::check_provider_availability,
         ai_commands::download_llamacpp_model,
         ai_commands::get_llamacpp_recommendation,
+        commands::get_cleaning_rules,
         commands::scan_junk,
         commands::scan_junk_with_options,
         commands::clean_junk,
         commands::clean_junk_with_options,
+        commands::scan_tool_caches,
+        commands::prune_tool_cache,
         execute_command::execute_command,
         // System Tools
         system_tools::get_disk_info,
@@ -0,0 +1,193 @@
+// Log files for the cleaner's "System Logs" category.
+//
+// Listing /var/log wholesale meant deleting logs that syslog, nginx and
+// friends still have open: the space only comes back when the daemon
+// restarts, and everything it writes until then is lost. Instead every file
+// under the log roots is classified by name:
+//
+// - rotated: logrotate's leftovers (`syslog.1`, `messages-20240101`,
+//   `dpkg.log.2.gz`, `Xorg.0.log.old`). Nothing writes to these, so they
+//   are deleted.
+// - active: anything else. These are truncated in place, which frees the
+//   space immediately and keeps the daemon's file handle valid. A writer
+//   that doesn't use O_APPEND keeps its offset and leaves a sparse file,
+//   same as logrotate's copytruncate.
+//
+// Login records (wtmp, btmp, lastlog, faillog) are left alone, and the
+// journal directory is handled by journald vacuum in tool_cleanup.rs.
+
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::cleaner::{get_file_age_days, CleaningOptions, JunkItem};
+
+pub const CATEGORY_ID: &str = "system_logs";
+pub const CATEGORY_NAME: &str = "System Logs";
+
+const COMPRESSED_EXTENSIONS: [&str; 6] = ["gz", "xz", "bz2", "zst", "lz4", "Z"];
+const LOGIN_RECORDS: [&str; 4] = ["wtmp", "btmp", "lastlog", "faillog"];
+const JOURNAL_DIR: &str = "journal";
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum LogKind {
+    /// Rotated or compressed; deleted.
+    Rotated,
+    /// Possibly still written to; truncated in place.
+    Active,
+}
+
+// Directories searched recursively, or single active log files.
+fn log_roots() -> Vec<PathBuf> {
+    let mut roots = Vec::new();
+    if cfg!(target_os = "linux") {
+        roots.push(PathBuf::from("/var/log"));
+        if let Some(home) = dirs::home_dir() {
+            roots.push(home.join(".xsession-errors"));
+        }
+    } else if cfg!(target_os = "macos") {
+        roots.push(PathBuf::from("/private/var/log"));
+    }
+    roots
+}
+
+/// Rotated and active logs under the log roots that pass the age filter.
+pub fn scan_logs(options: &CleaningOptions) -> Vec<JunkItem> {
+    let mut items: Vec<JunkItem> = log_roots().iter().flat_map(|root| scan_root(root)).collect();
+    if let Some(min_age) = options.min_age_days {
+        items.retain(|i| i.age_days.is_some_and(|age| age >= min_age));
+    }
+    items
+}
+
+fn scan_root(root: &Path) -> Vec<JunkItem> {
+    let mut items = Vec::new();
+    for entry in walkdir::WalkDir::new(root).into_iter().flatten() {
+        if !entry.file_type().is_file() {
+            continue;
+        }
+        let kind = match classify(root, entry.path()) {
+            Some(kind) => kind,
+            None => continue,
+        };
+        let meta = match entry.metadata() {
+            Ok(m) if m.len() > 0 => m,
+            _ => continue,
+        };
+        let name = match entry.path().strip_prefix(root) {
+            Ok(relative) if !relative.as_os_str().is_empty() => relative.to_string_lossy().to_string(),
+            _ => entry.file_name().to_string_lossy().to_string(),
+        };
+        items.push(JunkItem {
+            path: entry.path().to_string_lossy().to_string(),
+            name,
+            size: meta.len(),
+            description: match kind {
+                LogKind::Rotated => "Rotated log".to_string(),
+                LogKind::Active => "Active log, truncated in place".to_string(),
+            },
+            age_days: get_file_age_days(&meta),
+            project: None,
+            last_build: None,
+            log_kind: Some(kind),
+        });
+    }
+    items
+}
+
+// How `path` under `root` is cleaned, or None to leave it alone. A root
+// that is itself a file is an active log.
+fn classify(root: &Path, path: &Path) -> Option<LogKind> {
+    let relative = path.strip_prefix(root).ok()?;
+    if relative.as_os_str().is_empty() {
+        return Some(LogKind::Active);
+    }
+    if relative.components().next()?.as_os_str() == JOURNAL_DIR {
+        return None;
+    }
+    let name = path.file_name()?.to_str()?;
+    if is_rotated_log(name) {
+        Some(LogKind::Rotated)
+    } else if LOGIN_RECORDS.contains(&name) {
+        None
+    } else {
+        Some(LogKind::Active)
+    }
+}
+
+/// Whether `path` is an active log the cleaner must truncate rather than
+/// delete.
+pub fn is_active_log(path: &Path) -> bool {
+    log_roots().iter().any(|root| classify(root, path) == Some(LogKind::Active))
+}
+
+/// Empty a log without unlinking it, so whoever has it open keeps writing
+/// to the same file.
+pub fn truncate_log(path: &Path) -> std::io::Result<()> {
+    fs::OpenOptions::new().write(true).open(path)?.set_len(0)
+}
+
+// logrotate's naming: a numeric suffix (`.1`), a date suffix (`-20240101`,
+// `-2024010112`), `.old`, or compression on top of any of those.
+fn is_rotated_log(name: &str) -> bool {
+    let (stem, extension) = match name.rsplit_once('.') {
+        Some(split) => split,
+        None => return has_date_suffix(name),
+    };
+    if COMPRESSED_EXTENSIONS.contains(&extension) || extension == "old" {
+        return true;
+    }
+    (!extension.is_empty() && extension.bytes().all(|b| b.is_ascii_digit()) && !stem.is_empty())
+        || has_date_suffix(name)
+}
+
+fn has_date_suffix(name: &str) -> bool {
+    name.rsplit_once('-').is_some_and(|(stem, date)| {
+        !stem.is_empty() && (date.len() == 8 || date.len() == 10) && date.bytes().all(|b| b.is_ascii_digit())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TempDir;
+
+    #[test]
+    fn classifies_rotated_and_active_logs() {
+        for name in ["syslog.1", "dpkg.log.2.gz", "messages-20240101", "secure-2024010112.xz", "Xorg.0.log.old", "wtmp.1"] {
+            assert!(is_rotated_log(name), "{}", name);
+        }
+        for name in ["syslog", "Xorg.0.log", "dpkg.log", "kern.log", "boot.log-backup", "README"] {
+            assert!(!is_rotated_log(name), "{}", name);
+        }
+
+        let root = TempDir::new("logs");
+        fs::create_dir_all(root.join("nginx")).unwrap();
+        fs::create_dir_all(root.join("journal/abc")).unwrap();
+        fs::write(root.join("syslog"), b"live line\n").unwrap();
+        fs::write(root.join("syslog.1"), b"old line\n").unwrap();
+        fs::write(root.join("nginx/access.log.2.gz"), b"gz").unwrap();
+        fs::write(root.join("wtmp"), b"logins").unwrap();
+        fs::write(root.join("empty.log"), b"").unwrap();
+        fs::write(root.join("journal/abc/system.journal"), b"journal").unwrap();
+
+        let mut items = scan_root(&root);
+        items.sort_by(|a, b| a.name.cmp(&b.name));
+        let listed: Vec<(&str, Option<LogKind>)> = items.iter().map(|i| (i.name.as_str(), i.log_kind)).collect();
+        assert_eq!(
+            listed,
+            vec![
+                ("nginx/access.log.2.gz", Some(LogKind::Rotated)),
+                ("syslog", Some(LogKind::Active)),
+                ("syslog.1", Some(LogKind::Rotated)),
+            ]
+        );
+
+        // Truncation keeps the inode an open writer holds.
+        let mut writer = fs::OpenOptions::new().append(true).open(root.join("syslog")).unwrap();
+        truncate_log(&root.join("syslog")).unwrap();
+        std::io::Write::write_all(&mut writer, b"after\n").unwrap();
+        assert_eq!(fs::read(root.join("syslog")).unwrap(), b"after\n");
+    }
+}
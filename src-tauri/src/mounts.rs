@@ -0,0 +1,185 @@
+// Mount table helpers.
+//
+// Parses /proc/self/mountinfo on Linux so the scanner can recognise mount
+// points of pseudo filesystems (/proc, /sys, cgroup, ...) without a statfs
+// call per directory, and so the drive list can show where each volume comes
+// from. Other platforms report an empty table.
+
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use sysinfo::Disks;
+
+#[derive(Debug, Clone)]
+pub struct MountEntry {
+    pub mount_point: PathBuf,
+    pub fs_type: String,
+    /// Device or remote source, e.g. `/dev/nvme0n1p2` or `server:/export`.
+    pub source: String,
+    pub read_only: bool,
+}
+
+/// Kernel pseudo filesystems whose contents are not stored anywhere and whose
+/// reported sizes are meaningless (e.g. /proc/kcore).
+pub fn is_virtual_fs(fs_type: &str) -> bool {
+    matches!(
+        fs_type,
+        "proc" | "sysfs" | "devtmpfs" | "devpts" | "cgroup" | "cgroup2" | "debugfs" | "tracefs"
+            | "securityfs" | "pstore" | "bpf" | "configfs" | "fusectl" | "mqueue" | "hugetlbfs"
+            | "autofs" | "binfmt_misc" | "efivarfs" | "rpc_pipefs" | "nsfs"
+    )
+}
+
+/// Filesystems that hold no user data worth analysing: RAM-backed scratch
+/// space, container layers and read-only images such as snaps.
+pub fn is_pseudo_drive_fs(fs_type: &str) -> bool {
+    is_virtual_fs(fs_type) || matches!(fs_type, "tmpfs" | "ramfs" | "overlay" | "squashfs")
+}
+
+/// A mounted volume with its space usage, for the drive list.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct DriveInfo {
+    pub mount_point: String,
+    /// Volume name as sysinfo reports it, e.g. `Local Disk`; may be empty.
+    pub label: String,
+    /// Block device or remote source, e.g. `/dev/nvme0n1p2`.
+    pub device: String,
+    pub fs_type: String,
+    pub total: u64,
+    pub free: u64,
+    pub used: u64,
+    pub read_only: bool,
+    pub removable: bool,
+}
+
+/// Mounted volumes as reported by sysinfo, completed from the mount table.
+/// tmpfs, overlay, squashfs and kernel pseudo filesystems are left out
+/// unless `include_pseudo` is set.
+pub fn list_drives(include_pseudo: bool) -> Vec<DriveInfo> {
+    let mounts = read_mounts();
+    Disks::new_with_refreshed_list()
+        .iter()
+        .map(|disk| {
+            drive_info(
+                disk.mount_point(),
+                &disk.name().to_string_lossy(),
+                &disk.file_system().to_string_lossy(),
+                disk.total_space(),
+                disk.available_space(),
+                disk.is_removable(),
+                &mounts,
+            )
+        })
+        .filter(|drive| include_pseudo || !is_pseudo_drive_fs(&drive.fs_type))
+        .collect()
+}
+
+fn drive_info(
+    mount_point: &Path,
+    name: &str,
+    fs_type: &str,
+    total: u64,
+    free: u64,
+    removable: bool,
+    mounts: &[MountEntry],
+) -> DriveInfo {
+    // With stacked mounts the last entry is the one that is visible.
+    let entry = mounts.iter().rev().find(|m| m.mount_point == mount_point);
+    DriveInfo {
+        mount_point: mount_point.to_string_lossy().to_string(),
+        label: name.to_string(),
+        device: entry.map(|m| m.source.clone()).unwrap_or_else(|| name.to_string()),
+        fs_type: entry.map(|m| m.fs_type.clone()).unwrap_or_else(|| fs_type.to_string()),
+        total,
+        free,
+        used: total.saturating_sub(free),
+        read_only: entry.is_some_and(|m| m.read_only),
+        removable,
+    }
+}
+
+#[cfg(target_os = "linux")]
+pub fn read_mounts() -> Vec<MountEntry> {
+    std::fs::read_to_string("/proc/self/mountinfo")
+        .map(|s| s.lines().filter_map(parse_mountinfo_line).collect())
+        .unwrap_or_default()
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn read_mounts() -> Vec<MountEntry> {
+    Vec::new()
+}
+
+// Format (man 5 proc):
+// 36 35 98:0 /mnt1 /mnt/parent rw,noatime master:1 - ext3 /dev/root rw,errors=continue
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+fn parse_mountinfo_line(line: &str) -> Option<MountEntry> {
+    let (before, after) = line.split_once(" - ")?;
+    let fields: Vec<&str> = before.split(' ').collect();
+    let mount_point = unescape(fields.get(4)?);
+    let mount_options = fields.get(5)?;
+    let mut rest = after.split(' ');
+    let fs_type = rest.next()?.to_string();
+    let source = unescape(rest.next().unwrap_or(""));
+    Some(MountEntry {
+        mount_point: PathBuf::from(mount_point),
+        fs_type,
+        source,
+        read_only: mount_options.split(',').any(|o| o == "ro"),
+    })
+}
+
+// mountinfo escapes space, tab, newline and backslash as \ooo octal.
+fn unescape(field: &str) -> String {
+    let bytes = field.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let octal = bytes.get(i + 1..i + 4).filter(|d| d.iter().all(|b| (b'0'..=b'7').contains(b)));
+        if let (b'\\', Some(digits)) = (bytes[i], octal) {
+            let code = digits.iter().fold(0u32, |acc, d| acc * 8 + u32::from(d - b'0'));
+            out.push(code as u8);
+            i += 4;
+        } else {
+            out.push(bytes[i]);
+            i += 1;
+        }
+    }
+    String::from_utf8_lossy(&out).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_mountinfo_with_escaped_mount_point() {
+        let line = "36 35 98:0 / /mnt/my\\040disk ro,noatime master:1 - ext4 /dev/sdb1 rw,errors=continue";
+        let entry = parse_mountinfo_line(line).unwrap();
+        assert_eq!(entry.mount_point, PathBuf::from("/mnt/my disk"));
+        assert_eq!(entry.fs_type, "ext4");
+        assert_eq!(entry.source, "/dev/sdb1");
+        assert!(entry.read_only);
+        assert!(is_virtual_fs("proc"));
+        assert!(!is_virtual_fs(&entry.fs_type));
+    }
+
+    #[test]
+    fn drive_details_come_from_the_visible_mount() {
+        let mounts: Vec<MountEntry> = [
+            "22 1 8:1 / /data rw - ext4 /dev/sda1 rw",
+            "40 22 0:35 / /data ro - squashfs /dev/loop3 ro",
+        ]
+        .iter()
+        .filter_map(|l| parse_mountinfo_line(l))
+        .collect();
+        let drive = drive_info(Path::new("/data"), "sda1", "ext4", 100, 30, false, &mounts);
+        assert_eq!((drive.label.as_str(), drive.device.as_str()), ("sda1", "/dev/loop3"));
+        assert_eq!(drive.fs_type, "squashfs");
+        assert_eq!(drive.used, 70);
+        assert!(drive.read_only);
+        assert!(is_pseudo_drive_fs(&drive.fs_type));
+
+        let drive = drive_info(Path::new("C:\\"), "Local Disk", "NTFS", 100, 120, false, &[]);
+        assert_eq!((drive.device.as_str(), drive.used, drive.read_only), ("Local Disk", 0, false));
+    }
+}
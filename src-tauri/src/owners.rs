@@ -0,0 +1,136 @@
+// Disk usage per owner for scanned trees.
+//
+// Aggregates bytes and file counts per uid so admins of shared machines can
+// see which accounts are filling /home or /srv. Computed by the scanner when
+// ScanOptions::owners is set. User names come from /etc/passwd; accounts that
+// only exist in a directory service (LDAP, sssd) are reported by uid.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+pub struct OwnerStat {
+    pub bytes: u64,
+    pub count: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+pub struct OwnerBreakdown {
+    pub by_uid: HashMap<u32, OwnerStat>,
+}
+
+impl OwnerBreakdown {
+    pub fn add_file(&mut self, uid: u32, size: u64) {
+        let stat = self.by_uid.entry(uid).or_default();
+        stat.bytes += size;
+        stat.count += 1;
+    }
+
+    pub fn merge(&mut self, other: &OwnerBreakdown) {
+        for (uid, stat) in &other.by_uid {
+            let existing = self.by_uid.entry(*uid).or_default();
+            existing.bytes += stat.bytes;
+            existing.count += stat.count;
+        }
+    }
+
+    /// Inverse of `merge`; owners that drop to zero files are removed.
+    pub fn subtract(&mut self, other: &OwnerBreakdown) {
+        for (uid, stat) in &other.by_uid {
+            if let Some(existing) = self.by_uid.get_mut(uid) {
+                existing.bytes = existing.bytes.saturating_sub(stat.bytes);
+                existing.count = existing.count.saturating_sub(stat.count);
+                if existing.count == 0 {
+                    self.by_uid.remove(uid);
+                }
+            }
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct OwnerUsage {
+    pub uid: u32,
+    /// None when the uid has no local account (deleted user, LDAP).
+    pub name: Option<String>,
+    pub bytes: u64,
+    pub files: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct OwnerReport {
+    pub root: String,
+    pub total_bytes: u64,
+    /// Largest first.
+    pub owners: Vec<OwnerUsage>,
+}
+
+pub fn owner_report(root: &str, breakdown: &OwnerBreakdown) -> OwnerReport {
+    let names = user_names();
+    let mut owners: Vec<OwnerUsage> = breakdown
+        .by_uid
+        .iter()
+        .map(|(uid, stat)| OwnerUsage {
+            uid: *uid,
+            name: names.get(uid).cloned(),
+            bytes: stat.bytes,
+            files: stat.count,
+        })
+        .collect();
+    owners.sort_by_key(|o| (std::cmp::Reverse(o.bytes), o.uid));
+    OwnerReport {
+        root: root.to_string(),
+        total_bytes: owners.iter().map(|o| o.bytes).sum(),
+        owners,
+    }
+}
+
+fn user_names() -> HashMap<u32, String> {
+    std::fs::read_to_string("/etc/passwd")
+        .map(|s| parse_passwd(&s))
+        .unwrap_or_default()
+}
+
+// name:password:uid:gid:gecos:home:shell
+fn parse_passwd(contents: &str) -> HashMap<u32, String> {
+    let mut names = HashMap::new();
+    for line in contents.lines().filter(|l| !l.starts_with('#')) {
+        let mut fields = line.split(':');
+        if let (Some(name), Some(uid)) = (fields.next(), fields.nth(1)) {
+            if let Ok(uid) = uid.parse() {
+                // The first entry wins, as with getpwuid.
+                names.entry(uid).or_insert_with(|| name.to_string());
+            }
+        }
+    }
+    names
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn report_sorts_owners_and_resolves_names() {
+        let names = parse_passwd("root:x:0:0:root:/root:/bin/bash\n# comment\nana:x:1000:1000::/home/ana:/bin/sh\nalias:x:1000:1000::/:/bin/sh\n");
+        assert_eq!(names[&0], "root");
+        assert_eq!(names[&1000], "ana");
+
+        let mut a = OwnerBreakdown::default();
+        a.add_file(1000, 50);
+        let mut b = OwnerBreakdown::default();
+        b.add_file(0, 10);
+        b.add_file(1000, 5);
+        let original = a.clone();
+        a.merge(&b);
+        assert_eq!(a.by_uid[&1000], OwnerStat { bytes: 55, count: 2 });
+
+        let report = owner_report("/home", &a);
+        assert_eq!(report.total_bytes, 65);
+        assert_eq!(report.owners[0].uid, 1000);
+        assert_eq!(report.owners[1].bytes, 10);
+
+        a.subtract(&b);
+        assert_eq!(a, original);
+    }
+}
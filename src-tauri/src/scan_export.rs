@@ -0,0 +1,203 @@
+// Export a scanned FileNode tree for people who don't have the app.
+//
+// Three formats: flat CSV (one row per node, pre-order), nested JSON (the
+// FileNode shape itself), and a self-contained HTML treemap report with the
+// tree embedded as JSON, so helpdesk staff can attach a single file to a
+// ticket. All formats honour an optional depth limit (root = depth 0).
+
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+use crate::scanner::FileNode;
+
+const HTML_TEMPLATE: &str = include_str!("../templates/treemap_report.html");
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    Csv,
+    Json,
+    Html,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ExportResult {
+    pub destination: String,
+    pub format: ExportFormat,
+    pub nodes_written: u64,
+    pub bytes_written: u64,
+}
+
+pub fn export_tree(
+    tree: &FileNode,
+    format: ExportFormat,
+    destination: &Path,
+    max_depth: Option<u32>,
+) -> Result<ExportResult, String> {
+    let pruned = prune(tree, max_depth.unwrap_or(u32::MAX));
+    let file = File::create(destination)
+        .map_err(|e| format!("Cannot create {}: {}", destination.display(), e))?;
+    let mut out = BufWriter::new(file);
+
+    match format {
+        ExportFormat::Csv => write_csv(&pruned, &mut out).map_err(|e| e.to_string())?,
+        ExportFormat::Json => serde_json::to_writer_pretty(&mut out, &pruned).map_err(|e| e.to_string())?,
+        ExportFormat::Html => write_html(&pruned, &mut out)?,
+    }
+    out.flush().map_err(|e| e.to_string())?;
+    drop(out);
+
+    Ok(ExportResult {
+        destination: destination.to_string_lossy().to_string(),
+        format,
+        nodes_written: count_nodes(&pruned),
+        bytes_written: std::fs::metadata(destination).map(|m| m.len()).unwrap_or(0),
+    })
+}
+
+// Copy of `node` with children dropped below `depth`.
+fn prune(node: &FileNode, depth: u32) -> FileNode {
+    let children = if depth == 0 {
+        None
+    } else {
+        node.children.as_ref().map(|c| c.iter().map(|child| prune(child, depth - 1)).collect())
+    };
+    FileNode { children, ..node.clone() }
+}
+
+fn count_nodes(node: &FileNode) -> u64 {
+    1 + node.children.iter().flatten().map(count_nodes).sum::<u64>()
+}
+
+fn write_csv(node: &FileNode, out: &mut impl Write) -> std::io::Result<()> {
+    writeln!(out, "path,size,file_count,last_modified")?;
+    write_csv_rows(node, out)
+}
+
+fn write_csv_rows(node: &FileNode, out: &mut impl Write) -> std::io::Result<()> {
+    writeln!(
+        out,
+        "{},{},{},{}",
+        csv_field(&node.path),
+        node.size,
+        node.file_count,
+        format_timestamp(node.last_modified),
+    )?;
+    for child in node.children.iter().flatten() {
+        write_csv_rows(child, out)?;
+    }
+    Ok(())
+}
+
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+fn format_timestamp(secs: u64) -> String {
+    chrono::DateTime::from_timestamp(secs as i64, 0)
+        .map(|t| t.to_rfc3339_opts(chrono::SecondsFormat::Secs, true))
+        .unwrap_or_default()
+}
+
+fn write_html(node: &FileNode, out: &mut impl Write) -> Result<(), String> {
+    // Keep the embedded JSON from closing its <script> element early.
+    let data = serde_json::to_string(node).map_err(|e| e.to_string())?.replace("</", "<\\/");
+    let generated = chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, true);
+    let html = fill_template(HTML_TEMPLATE, &[
+        ("TITLE", &html_escape(&node.path)),
+        ("GENERATED", &generated),
+        ("DATA", &data),
+    ]);
+    out.write_all(html.as_bytes()).map_err(|e| e.to_string())
+}
+
+// Replaces each `{{KEY}}` in one pass, so a value that itself contains a
+// placeholder (a folder named `{{DATA}}`) is left as-is.
+fn fill_template(template: &str, values: &[(&str, &str)]) -> String {
+    let mut out = String::with_capacity(template.len() + values.iter().map(|(_, v)| v.len()).sum::<usize>());
+    let mut rest = template;
+    while let Some(start) = rest.find("{{") {
+        out.push_str(&rest[..start]);
+        let after = &rest[start + 2..];
+        let value = after
+            .find("}}")
+            .and_then(|end| values.iter().find(|(key, _)| *key == &after[..end]).map(|(key, v)| (key.len(), v)));
+        match value {
+            Some((key_len, value)) => {
+                out.push_str(value);
+                rest = &after[key_len + 2..];
+            }
+            None => {
+                out.push_str("{{");
+                rest = after;
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+fn html_escape(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn node(path: &str, size: u64, children: Option<Vec<FileNode>>) -> FileNode {
+        FileNode {
+            name: path.rsplit('/').next().unwrap_or_default().to_string(),
+            path: path.to_string(),
+            size,
+            is_dir: children.is_some(),
+            file_count: 1,
+            children,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn csv_respects_depth_and_quotes_paths() {
+        let tree = node("/r", 30, Some(vec![
+            node("/r/a,b", 20, Some(vec![node("/r/a,b/deep", 20, None)])),
+            node("/r/c", 10, None),
+        ]));
+        let mut out = Vec::new();
+        write_csv(&prune(&tree, 1), &mut out).unwrap();
+        let csv = String::from_utf8(out).unwrap();
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines.len(), 4);
+        assert_eq!(lines[2], "\"/r/a,b\",20,1,1970-01-01T00:00:00Z");
+        assert!(!csv.contains("deep"));
+    }
+
+    #[test]
+    fn html_embeds_escaped_tree() {
+        let tree = node("/r/</script>", 1, Some(vec![]));
+        let mut out = Vec::new();
+        write_html(&tree, &mut out).unwrap();
+        let html = String::from_utf8(out).unwrap();
+        assert!(html.contains("<title>Disk usage: /r/&lt;/script&gt;</title>"));
+        assert!(html.contains("\"/r/<\\/script>\""));
+        assert_eq!(html.matches("</script>").count(), 2);
+
+        // Placeholders inside values aren't expanded.
+        let tree = node("/r/{{DATA}}", 1, Some(vec![]));
+        let mut out = Vec::new();
+        write_html(&tree, &mut out).unwrap();
+        let html = String::from_utf8(out).unwrap();
+        assert!(html.contains("<title>Disk usage: /r/{{DATA}}</title>"));
+        assert_eq!(html.matches("id=\"tree\">{\"").count(), 1);
+    }
+}
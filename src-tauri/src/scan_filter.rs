@@ -0,0 +1,154 @@
+// Which entries a scan descends into.
+//
+// Compiled once per scan from ScanOptions: the root's device and the mount
+// points below it (for stay-on-one-filesystem), the mount points of pseudo
+// filesystems, and the user's exclusion globs. Patterns without a `/` match an entry's name at any
+// depth (`node_modules`, `*.iso`); patterns with one match the full path
+// (`/home/*/.cache`, `**/target/debug`).
+
+use glob::{MatchOptions, Pattern};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+use crate::mounts;
+use crate::scanner::ScanOptions;
+
+const MATCH_OPTIONS: MatchOptions = MatchOptions {
+    case_sensitive: true,
+    require_literal_separator: true,
+    require_literal_leading_dot: false,
+};
+
+#[derive(Default)]
+pub struct ScanFilter {
+    root_dev: Option<u64>,
+    /// Every mount point below the root, when staying on one filesystem. A
+    /// bind mount has the same device as its source, so `root_dev` alone
+    /// would walk into it and count its contents twice.
+    mount_points: HashSet<PathBuf>,
+    virtual_mounts: HashSet<PathBuf>,
+    name_patterns: Vec<Pattern>,
+    path_patterns: Vec<Pattern>,
+}
+
+impl ScanFilter {
+    pub fn new(root: &Path, options: &ScanOptions) -> Result<Self, String> {
+        let mut filter = ScanFilter::default();
+        let mounts = if options.one_file_system || options.skip_virtual_fs {
+            mounts::read_mounts()
+        } else {
+            Vec::new()
+        };
+
+        if options.one_file_system {
+            filter.root_dev = std::fs::metadata(root).ok().and_then(|m| device_of(&m));
+            filter.mount_points = mounts_below(root, &mounts);
+        }
+        if options.skip_virtual_fs {
+            filter.virtual_mounts = mounts
+                .into_iter()
+                .filter(|m| mounts::is_virtual_fs(&m.fs_type))
+                .map(|m| m.mount_point)
+                // Scanning /proc itself on purpose is allowed.
+                .filter(|p| !root.starts_with(p))
+                .collect();
+        }
+        for raw in options.exclude.iter().map(|p| p.trim()).filter(|p| !p.is_empty()) {
+            let pattern = Pattern::new(raw.trim_end_matches('/'))
+                .map_err(|e| format!("Invalid exclude pattern '{}': {}", raw, e))?;
+            if raw.trim_end_matches('/').contains('/') {
+                filter.path_patterns.push(pattern);
+            } else {
+                filter.name_patterns.push(pattern);
+            }
+        }
+        Ok(filter)
+    }
+
+    /// Whether the scan should count `path` (and, for directories, descend
+    /// into it). `meta` must be the entry's own (non-followed) metadata.
+    pub fn allows(&self, path: &Path, meta: &std::fs::Metadata) -> bool {
+        if meta.is_dir() {
+            if let (Some(root_dev), Some(dev)) = (self.root_dev, device_of(meta)) {
+                if dev != root_dev {
+                    return false;
+                }
+            }
+            if self.mount_points.contains(path) || self.virtual_mounts.contains(path) {
+                return false;
+            }
+        }
+        !self.is_excluded(path)
+    }
+
+    fn is_excluded(&self, path: &Path) -> bool {
+        if let Some(name) = path.file_name().and_then(|n| n.to_str()) {
+            if self.name_patterns.iter().any(|p| p.matches_with(name, MATCH_OPTIONS)) {
+                return true;
+            }
+        }
+        self.path_patterns.iter().any(|p| p.matches_path_with(path, MATCH_OPTIONS))
+    }
+}
+
+fn mounts_below(root: &Path, mounts: &[mounts::MountEntry]) -> HashSet<PathBuf> {
+    mounts
+        .iter()
+        .map(|m| m.mount_point.clone())
+        .filter(|p| p != root && p.starts_with(root))
+        .collect()
+}
+
+#[cfg(unix)]
+fn device_of(meta: &std::fs::Metadata) -> Option<u64> {
+    use std::os::unix::fs::MetadataExt;
+    Some(meta.dev())
+}
+
+#[cfg(not(unix))]
+fn device_of(_meta: &std::fs::Metadata) -> Option<u64> {
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn name_and_path_patterns() {
+        let options = ScanOptions {
+            exclude: vec!["node_modules".into(), "*.iso".into(), "/home/*/.cache/".into()],
+            ..Default::default()
+        };
+        let filter = ScanFilter::new(Path::new("/"), &options).unwrap();
+        assert!(filter.is_excluded(Path::new("/src/app/node_modules")));
+        assert!(filter.is_excluded(Path::new("/data/ubuntu.iso")));
+        assert!(filter.is_excluded(Path::new("/home/ana/.cache")));
+        assert!(!filter.is_excluded(Path::new("/home/ana/sub/.cache")));
+        assert!(!filter.is_excluded(Path::new("/src/app/node_modules_backup")));
+    }
+
+    #[test]
+    fn mount_points_below_the_root_are_boundaries() {
+        let mount = |path: &str| mounts::MountEntry {
+            mount_point: PathBuf::from(path),
+            fs_type: "ext4".to_string(),
+            source: "/dev/sda1".to_string(),
+            read_only: false,
+        };
+        // /data/bind is a bind mount of another /data directory: same device.
+        let table = [mount("/"), mount("/data"), mount("/data/bind"), mount("/other")];
+        let filter = ScanFilter { mount_points: mounts_below(Path::new("/data"), &table), ..Default::default() };
+        assert_eq!(filter.mount_points, HashSet::from([PathBuf::from("/data/bind")]));
+
+        let dir = std::fs::metadata(std::env::temp_dir()).unwrap();
+        assert!(!filter.allows(Path::new("/data/bind"), &dir));
+        assert!(filter.allows(Path::new("/data/projects"), &dir));
+    }
+
+    #[test]
+    fn rejects_invalid_pattern() {
+        let options = ScanOptions { exclude: vec!["[".into()], ..Default::default() };
+        assert!(ScanFilter::new(Path::new("/"), &options).is_err());
+    }
+}
@@ -0,0 +1,285 @@
+// SQLite-backed persistent index of storage-analyzer scan results.
+//
+// SCAN_CACHE in commands.rs only lives as long as the process, so every
+// restart re-walked whole drives. Each cached FileNode is mirrored here,
+// keyed by its normalized path, with the directory mtimes/inodes recorded at
+// scan time. The index is loaded into SCAN_CACHE on startup as the baseline
+// for an incremental rescan (see commands::refresh_scan_incremental); loaded
+// entries are never served as current results by themselves.
+//
+// Lives in its own database file next to the workflow DB so that writing
+// large scans never contends with workflow run checkpoints.
+//
+// Tables:
+//   scan_index_entries — one row per cached node (root + lookahead children),
+//                        with the ScanOptions it was built with
+//   scan_snapshots     — named, frozen copies of a scan tree for diffing
+
+use crate::scanner::{FileNode, ScanOptions};
+use crate::snapshots::SnapshotInfo;
+use rusqlite::{params, Connection};
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+pub struct ScanIndex {
+    conn: Mutex<Connection>,
+}
+
+/// A cached node as stored on disk. `scanned_at` is seconds since the epoch.
+pub struct IndexedEntry {
+    pub key: String,
+    pub node: FileNode,
+    pub options: ScanOptions,
+    pub scanned_at: u64,
+}
+
+impl ScanIndex {
+    pub fn new() -> Result<Self, String> {
+        let path = Self::db_path()?;
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+        }
+        let conn = Connection::open(&path).map_err(|e| e.to_string())?;
+        conn.execute_batch("PRAGMA journal_mode = WAL;")
+            .map_err(|e| e.to_string())?;
+        Self::run_migrations(&conn)?;
+        Ok(Self {
+            conn: Mutex::new(conn),
+        })
+    }
+
+    fn db_path() -> Result<PathBuf, String> {
+        let home = dirs::home_dir().ok_or_else(|| "Could not resolve home directory".to_string())?;
+        Ok(home.join(".ittoolkit/scan_index.db"))
+    }
+
+    fn run_migrations(conn: &Connection) -> Result<(), String> {
+        let version: u32 = conn
+            .query_row("PRAGMA user_version", [], |row| row.get(0))
+            .map_err(|e| e.to_string())?;
+
+        if version < 1 {
+            conn.execute_batch(include_str!("../migrations/scan_index_001.sql"))
+                .map_err(|e| e.to_string())?;
+            conn.execute_batch("PRAGMA user_version = 1")
+                .map_err(|e| e.to_string())?;
+        }
+
+        if version < 2 {
+            conn.execute_batch(include_str!("../migrations/scan_index_002.sql"))
+                .map_err(|e| e.to_string())?;
+            conn.execute_batch("PRAGMA user_version = 2")
+                .map_err(|e| e.to_string())?;
+        }
+
+        if version < 3 {
+            conn.execute_batch(include_str!("../migrations/scan_index_003.sql"))
+                .map_err(|e| e.to_string())?;
+            conn.execute_batch("PRAGMA user_version = 3")
+                .map_err(|e| e.to_string())?;
+        }
+
+        if version < 4 {
+            conn.execute_batch(include_str!("../migrations/scan_index_004.sql"))
+                .map_err(|e| e.to_string())?;
+            conn.execute_batch("PRAGMA user_version = 4")
+                .map_err(|e| e.to_string())?;
+        }
+
+        if version < 5 {
+            conn.execute_batch(include_str!("../migrations/scan_index_005.sql"))
+                .map_err(|e| e.to_string())?;
+            conn.execute_batch("PRAGMA user_version = 5")
+                .map_err(|e| e.to_string())?;
+        }
+
+        Ok(())
+    }
+
+    /// Load every stored entry. Rows whose JSON no longer deserializes (e.g.
+    /// written by an older build) are skipped rather than failing startup.
+    pub fn load_all(&self) -> Result<Vec<IndexedEntry>, String> {
+        let conn = self.conn.lock().map_err(|e| e.to_string())?;
+        let mut stmt = conn
+            .prepare("SELECT path, node_json, options_json, scanned_at FROM scan_index_entries")
+            .map_err(|e| e.to_string())?;
+
+        let rows: Vec<(String, String, String, i64)> = stmt
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?)))
+            .map_err(|e| e.to_string())?
+            .filter_map(|r| r.ok())
+            .collect();
+
+        Ok(rows
+            .into_iter()
+            .filter_map(|(key, node_json, options_json, scanned_at)| {
+                let node: FileNode = serde_json::from_str(&node_json).ok()?;
+                let options: ScanOptions = serde_json::from_str(&options_json).ok()?;
+                Some(IndexedEntry {
+                    key,
+                    node,
+                    options,
+                    scanned_at: scanned_at.max(0) as u64,
+                })
+            })
+            .collect())
+    }
+
+    /// Insert or replace a batch of entries in a single transaction.
+    pub fn upsert_many(&self, entries: &[IndexedEntry]) -> Result<(), String> {
+        let mut conn = self.conn.lock().map_err(|e| e.to_string())?;
+        let tx = conn.transaction().map_err(|e| e.to_string())?;
+        for entry in entries {
+            let node_json = serde_json::to_string(&entry.node).map_err(|e| e.to_string())?;
+            let options_json = serde_json::to_string(&entry.options).map_err(|e| e.to_string())?;
+            tx.execute(
+                "INSERT INTO scan_index_entries (path, mtime, inode, node_json, options_json, scanned_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+                 ON CONFLICT(path) DO UPDATE SET
+                   mtime        = excluded.mtime,
+                   inode        = excluded.inode,
+                   node_json    = excluded.node_json,
+                   options_json = excluded.options_json,
+                   scanned_at   = excluded.scanned_at",
+                params![
+                    entry.key,
+                    entry.node.last_modified as i64,
+                    entry.node.inode.map(|i| i as i64),
+                    node_json,
+                    options_json,
+                    entry.scanned_at as i64,
+                ],
+            )
+            .map_err(|e| e.to_string())?;
+        }
+        tx.commit().map_err(|e| e.to_string())?;
+        Ok(())
+    }
+
+    pub fn remove(&self, keys: &[String]) -> Result<(), String> {
+        let conn = self.conn.lock().map_err(|e| e.to_string())?;
+        for key in keys {
+            conn.execute("DELETE FROM scan_index_entries WHERE path = ?1", params![key])
+                .map_err(|e| e.to_string())?;
+        }
+        Ok(())
+    }
+
+    pub fn clear(&self) -> Result<(), String> {
+        let conn = self.conn.lock().map_err(|e| e.to_string())?;
+        conn.execute("DELETE FROM scan_index_entries", [])
+            .map_err(|e| e.to_string())?;
+        Ok(())
+    }
+
+    // ── Snapshots ──
+
+    /// Save `tree` as snapshot `name` of `root`, replacing an existing
+    /// snapshot with the same name.
+    pub fn save_snapshot(
+        &self,
+        name: &str,
+        root: &str,
+        options: &ScanOptions,
+        tree: &FileNode,
+        created_at: u64,
+    ) -> Result<SnapshotInfo, String> {
+        let tree_json = serde_json::to_string(tree).map_err(|e| e.to_string())?;
+        let options_json = serde_json::to_string(options).map_err(|e| e.to_string())?;
+        let conn = self.conn.lock().map_err(|e| e.to_string())?;
+        conn.execute(
+            "INSERT INTO scan_snapshots (name, root, created_at, size, file_count, options_json, tree_json)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)
+             ON CONFLICT(root, name) DO UPDATE SET
+               created_at   = excluded.created_at,
+               size         = excluded.size,
+               file_count   = excluded.file_count,
+               options_json = excluded.options_json,
+               tree_json    = excluded.tree_json",
+            params![
+                name,
+                root,
+                created_at as i64,
+                tree.size as i64,
+                tree.file_count as i64,
+                options_json,
+                tree_json,
+            ],
+        )
+        .map_err(|e| e.to_string())?;
+        let id: i64 = conn
+            .query_row(
+                "SELECT id FROM scan_snapshots WHERE root = ?1 AND name = ?2",
+                params![root, name],
+                |row| row.get(0),
+            )
+            .map_err(|e| e.to_string())?;
+        Ok(SnapshotInfo {
+            id,
+            name: name.to_string(),
+            root: root.to_string(),
+            created_at,
+            size: tree.size,
+            file_count: tree.file_count,
+        })
+    }
+
+    /// Snapshots, newest first, optionally only those of `root`.
+    pub fn list_snapshots(&self, root: Option<&str>) -> Result<Vec<SnapshotInfo>, String> {
+        let conn = self.conn.lock().map_err(|e| e.to_string())?;
+        let mut stmt = conn
+            .prepare(
+                "SELECT id, name, root, created_at, size, file_count FROM scan_snapshots
+                 WHERE ?1 IS NULL OR root = ?1
+                 ORDER BY created_at DESC, id DESC",
+            )
+            .map_err(|e| e.to_string())?;
+        let rows = stmt
+            .query_map(params![root], |row| {
+                Ok(SnapshotInfo {
+                    id: row.get(0)?,
+                    name: row.get(1)?,
+                    root: row.get(2)?,
+                    created_at: row.get::<_, i64>(3)?.max(0) as u64,
+                    size: row.get::<_, i64>(4)?.max(0) as u64,
+                    file_count: row.get::<_, i64>(5)?.max(0) as u64,
+                })
+            })
+            .map_err(|e| e.to_string())?
+            .filter_map(|r| r.ok())
+            .collect();
+        Ok(rows)
+    }
+
+    pub fn load_snapshot(&self, id: i64) -> Result<(SnapshotInfo, ScanOptions, FileNode), String> {
+        let conn = self.conn.lock().map_err(|e| e.to_string())?;
+        let (info, options_json, tree_json) = conn
+            .query_row(
+                "SELECT id, name, root, created_at, size, file_count, options_json, tree_json
+                 FROM scan_snapshots WHERE id = ?1",
+                params![id],
+                |row| {
+                    let info = SnapshotInfo {
+                        id: row.get(0)?,
+                        name: row.get(1)?,
+                        root: row.get(2)?,
+                        created_at: row.get::<_, i64>(3)?.max(0) as u64,
+                        size: row.get::<_, i64>(4)?.max(0) as u64,
+                        file_count: row.get::<_, i64>(5)?.max(0) as u64,
+                    };
+                    Ok((info, row.get::<_, String>(6)?, row.get::<_, String>(7)?))
+                },
+            )
+            .map_err(|e| format!("Snapshot {} not found: {}", id, e))?;
+        let options = serde_json::from_str(&options_json).map_err(|e| e.to_string())?;
+        let tree = serde_json::from_str(&tree_json).map_err(|e| e.to_string())?;
+        Ok((info, options, tree))
+    }
+
+    pub fn delete_snapshot(&self, id: i64) -> Result<(), String> {
+        let conn = self.conn.lock().map_err(|e| e.to_string())?;
+        conn.execute("DELETE FROM scan_snapshots WHERE id = ?1", params![id])
+            .map_err(|e| e.to_string())?;
+        Ok(())
+    }
+}
@@ -0,0 +1,325 @@
+// Live filesystem watching for scanned trees.
+//
+// Once a folder has been scanned its FileNode tree in SCAN_CACHE goes stale
+// as soon as anything is written. `watch_scan_tree` puts an inotify watch on
+// every directory of the cached tree that carries a children list, applies
+// create/delete/resize events to the cached nodes (re-aggregating sizes up
+// the ancestor chain), and emits `scan-tree-updated` so the UI can re-read.
+//
+// Deep aggregate directories (children: None) get one watch on their top
+// directory, as do directories created while watching. An event there can't
+// be applied to a single node, so the aggregate is re-measured as a whole.
+// inotify is not recursive and watching a whole volume would exhaust
+// fs.inotify.max_user_watches, so changes further down an aggregate still
+// need a rescan (`refresh_scan_incremental` finds them).
+//
+// Linux only for now; other platforms return an error.
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use tauri::{command, AppHandle};
+
+lazy_static::lazy_static! {
+    // Stop flags for running watchers, keyed by the watched root path.
+    static ref WATCHERS: Mutex<HashMap<String, Arc<AtomicBool>>> = Mutex::new(HashMap::new());
+}
+
+#[derive(Clone, serde::Serialize)]
+struct ScanTreeUpdated {
+    root: String,
+    /// Paths created, deleted or resized since the previous event.
+    paths: Vec<String>,
+    /// The kernel dropped events; the cached tree may be incomplete and
+    /// should be rescanned.
+    overflow: bool,
+}
+
+#[command]
+pub fn watch_scan_tree(app: AppHandle, path: String) -> Result<(), String> {
+    let (tree, options) = crate::commands::cached_tree(&path)
+        .ok_or_else(|| "Path has not been scanned yet".to_string())?;
+
+    let mut watchers = WATCHERS.lock().map_err(|e| e.to_string())?;
+    if watchers.contains_key(&tree.path) {
+        return Ok(());
+    }
+
+    let stop = Arc::new(AtomicBool::new(false));
+    platform::spawn_watcher(app, &tree, options, stop.clone())?;
+    watchers.insert(tree.path.clone(), stop);
+    Ok(())
+}
+
+#[command]
+pub fn unwatch_scan_tree(path: String) -> Result<(), String> {
+    let tree_path = crate::commands::cached_tree(&path)
+        .map(|(t, _)| t.path)
+        .unwrap_or(path);
+    let mut watchers = WATCHERS.lock().map_err(|e| e.to_string())?;
+    if let Some(stop) = watchers.remove(&tree_path) {
+        stop.store(true, Ordering::Relaxed);
+    }
+    Ok(())
+}
+
+// Directories of the cached tree that have a children list, i.e. the ones
+// whose direct entries we can update in place, then the deep aggregates
+// directly below them.
+fn watched_dirs(tree: &crate::scanner::FileNode) -> Vec<String> {
+    fn walk(node: &crate::scanner::FileNode, listed: &mut Vec<String>, aggregates: &mut Vec<String>) {
+        let Some(children) = &node.children else {
+            return;
+        };
+        listed.push(node.path.clone());
+        for child in children.iter().filter(|c| c.is_dir) {
+            if child.children.is_some() {
+                walk(child, listed, aggregates);
+            } else {
+                aggregates.push(child.path.clone());
+            }
+        }
+    }
+    let (mut listed, mut aggregates) = (Vec::new(), Vec::new());
+    walk(tree, &mut listed, &mut aggregates);
+    listed.append(&mut aggregates);
+    listed
+}
+
+#[cfg(target_os = "linux")]
+mod platform {
+    use super::{watched_dirs, ScanTreeUpdated};
+    use crate::scanner::{measure_path, FileNode, ScanOptions};
+    use std::collections::{HashMap, HashSet};
+    use std::ffi::CString;
+    use std::fs::File;
+    use std::io::Read;
+    use std::os::unix::ffi::OsStrExt;
+    use std::os::unix::io::{AsRawFd, FromRawFd};
+    use std::path::PathBuf;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::Arc;
+    use std::time::{Duration, Instant};
+    use tauri::{AppHandle, Emitter};
+
+    const WATCH_MASK: u32 = libc::IN_CREATE
+        | libc::IN_DELETE
+        | libc::IN_CLOSE_WRITE
+        | libc::IN_MODIFY
+        | libc::IN_MOVED_FROM
+        | libc::IN_MOVED_TO
+        | libc::IN_DELETE_SELF
+        | libc::IN_ONLYDIR;
+
+    // Well under the usual fs.inotify.max_user_watches (8192 on older
+    // kernels), which other apps share. Listed directories come first.
+    const MAX_WATCHES: usize = 4096;
+
+    // inotify_event header: wd (i32), mask, cookie, len (u32 each).
+    const EVENT_HEADER_LEN: usize = 16;
+
+    // IN_MODIFY fires on every write; coalesce a burst into one update.
+    const DEBOUNCE: Duration = Duration::from_millis(500);
+    // Under steady writes (a growing log, a build) the burst never ends;
+    // flush anyway once the oldest pending change has waited this long.
+    const MAX_LATENCY: Duration = Duration::from_secs(3);
+
+    pub fn spawn_watcher(
+        app: AppHandle,
+        tree: &FileNode,
+        options: ScanOptions,
+        stop: Arc<AtomicBool>,
+    ) -> Result<(), String> {
+        let fd = unsafe { libc::inotify_init1(libc::IN_NONBLOCK | libc::IN_CLOEXEC) };
+        if fd < 0 {
+            return Err(format!("inotify_init1 failed: {}", std::io::Error::last_os_error()));
+        }
+        // Owning the fd as a File closes it when the watcher thread exits.
+        let file = unsafe { File::from_raw_fd(fd) };
+
+        let mut watches: HashMap<i32, PathBuf> = HashMap::new();
+        for dir in watched_dirs(tree).into_iter().take(MAX_WATCHES) {
+            add_watch(fd, PathBuf::from(dir), &mut watches);
+        }
+        if watches.is_empty() {
+            return Err("No directories could be watched".to_string());
+        }
+
+        let root = tree.path.clone();
+        std::thread::spawn(move || run(app, root, options, file, watches, stop));
+        Ok(())
+    }
+
+    fn add_watch(fd: i32, dir: PathBuf, watches: &mut HashMap<i32, PathBuf>) {
+        if watches.len() >= MAX_WATCHES {
+            return;
+        }
+        let c_path = match CString::new(dir.as_os_str().as_bytes()) {
+            Ok(p) => p,
+            Err(_) => return,
+        };
+        let wd = unsafe { libc::inotify_add_watch(fd, c_path.as_ptr(), WATCH_MASK) };
+        if wd < 0 {
+            log::warn!("[scan_watcher] cannot watch {}: {}", dir.display(), std::io::Error::last_os_error());
+            return;
+        }
+        watches.insert(wd, dir);
+    }
+
+    fn run(
+        app: AppHandle,
+        root: String,
+        options: ScanOptions,
+        mut file: File,
+        mut watches: HashMap<i32, PathBuf>,
+        stop: Arc<AtomicBool>,
+    ) {
+        let mut buf = vec![0u8; 64 * 1024];
+        let mut pending: HashSet<PathBuf> = HashSet::new();
+        let mut last_event = Instant::now();
+        // When the oldest unflushed change arrived.
+        let mut first_pending: Option<Instant> = None;
+        let mut overflow = false;
+
+        while !stop.load(Ordering::Relaxed) && !watches.is_empty() {
+            let mut pfd = libc::pollfd { fd: file.as_raw_fd(), events: libc::POLLIN, revents: 0 };
+            let ready = unsafe { libc::poll(&mut pfd, 1, 250) };
+
+            if ready > 0 {
+                match file.read(&mut buf) {
+                    Ok(n) => {
+                        let fd = file.as_raw_fd();
+                        parse_events(fd, &buf[..n], &mut watches, &mut pending, &mut overflow);
+                        last_event = Instant::now();
+                        if !pending.is_empty() || overflow {
+                            first_pending.get_or_insert(last_event);
+                        }
+                    }
+                    Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => {}
+                    Err(e) => {
+                        log::error!("[scan_watcher] read failed for {}: {}", root, e);
+                        break;
+                    }
+                }
+            }
+
+            let overdue = first_pending.is_some_and(|t| t.elapsed() >= MAX_LATENCY);
+            if (!pending.is_empty() || overflow) && (last_event.elapsed() >= DEBOUNCE || overdue) {
+                flush(&app, &root, &options, &mut pending, &mut overflow);
+                first_pending = None;
+            }
+        }
+
+        if !pending.is_empty() || overflow {
+            flush(&app, &root, &options, &mut pending, &mut overflow);
+        }
+
+        // Deregister ourselves unless a newer watcher already replaced us.
+        if let Ok(mut watchers) = super::WATCHERS.lock() {
+            if watchers.get(&root).map(|s| Arc::ptr_eq(s, &stop)).unwrap_or(false) {
+                watchers.remove(&root);
+            }
+        }
+        log::info!("[scan_watcher] stopped watching {}", root);
+    }
+
+    fn parse_events(
+        fd: i32,
+        data: &[u8],
+        watches: &mut HashMap<i32, PathBuf>,
+        pending: &mut HashSet<PathBuf>,
+        overflow: &mut bool,
+    ) {
+        let mut offset = 0;
+        while offset + EVENT_HEADER_LEN <= data.len() {
+            let field = |at: usize| {
+                let mut b = [0u8; 4];
+                b.copy_from_slice(&data[offset + at..offset + at + 4]);
+                b
+            };
+            let wd = i32::from_ne_bytes(field(0));
+            let mask = u32::from_ne_bytes(field(4));
+            let name_len = u32::from_ne_bytes(field(12)) as usize;
+            let name_start = offset + EVENT_HEADER_LEN;
+            let name_end = (name_start + name_len).min(data.len());
+            offset = name_end;
+
+            if mask & libc::IN_Q_OVERFLOW != 0 {
+                *overflow = true;
+                continue;
+            }
+            if mask & (libc::IN_IGNORED | libc::IN_DELETE_SELF) != 0 {
+                watches.remove(&wd);
+                continue;
+            }
+
+            let dir = match watches.get(&wd) {
+                Some(d) => d,
+                None => continue,
+            };
+            let raw_name = &data[name_start..name_end];
+            let name = raw_name.split(|b| *b == 0).next().unwrap_or(&[]);
+            if name.is_empty() {
+                continue;
+            }
+            let path = dir.join(std::ffi::OsStr::from_bytes(name));
+            // New directories get a watch too, so files written into them
+            // right after creation aren't missed.
+            if mask & libc::IN_ISDIR != 0 && mask & (libc::IN_CREATE | libc::IN_MOVED_TO) != 0 {
+                add_watch(fd, path.clone(), watches);
+            }
+            pending.insert(path);
+        }
+    }
+
+    fn flush(
+        app: &AppHandle,
+        root: &str,
+        options: &ScanOptions,
+        pending: &mut HashSet<PathBuf>,
+        overflow: &mut bool,
+    ) {
+        // Events inside a deep aggregate re-measure the aggregate, once per
+        // flush however many of its entries changed.
+        let targets: HashSet<PathBuf> =
+            pending.drain().flat_map(|target| crate::commands::cached_update_points(&target)).collect();
+        let mut paths = Vec::new();
+        for target in targets {
+            // Re-stat rather than trusting the event kind: a create followed
+            // by a delete inside the debounce window is simply gone.
+            let node = measure_path(&target, options);
+            let updated = crate::commands::update_cached_trees(&target, node.as_ref());
+            if !updated.is_empty() {
+                paths.push(target.to_string_lossy().to_string());
+            }
+        }
+
+        if paths.is_empty() && !*overflow {
+            return;
+        }
+        paths.sort();
+        let _ = app.emit("scan-tree-updated", ScanTreeUpdated {
+            root: root.to_string(),
+            paths,
+            overflow: *overflow,
+        });
+        *overflow = false;
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+mod platform {
+    use crate::scanner::{FileNode, ScanOptions};
+    use std::sync::atomic::AtomicBool;
+    use std::sync::Arc;
+    use tauri::AppHandle;
+
+    pub fn spawn_watcher(
+        _app: AppHandle,
+        _tree: &FileNode,
+        _options: ScanOptions,
+        _stop: Arc<AtomicBool>,
+    ) -> Result<(), String> {
+        Err("Live filesystem watching is only supported on Linux".to_string())
+    }
+}
@@ -3,301 +3,1462 @@ use serde::{Deserialize, Serialize};
 use std::time::SystemTime;
 use rayon::prelude::*;
 use std::sync::{Arc, atomic::{AtomicBool, AtomicU64, Ordering}};
+use crate::file_types::FileTypeBreakdown;
+use crate::owners::OwnerBreakdown;
+use crate::scan_filter::ScanFilter;
+use crate::mounts::DriveInfo;
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
 pub struct FileNode {
     pub name: String,
     pub path: String,
+    /// Size the UI totals use: apparent or allocated, per `ScanOptions::size_mode`.
     pub size: u64,
+    /// Sum of file lengths (`st_size`).
+    #[serde(default)]
+    pub apparent_size: u64,
+    /// Bytes actually allocated on disk (`st_blocks * 512` on Unix). Smaller
+    /// than `apparent_size` for sparse files such as VM images.
+    #[serde(default)]
+    pub allocated_size: u64,
     pub is_dir: bool,
+    /// Symlinks are their own kind; `is_dir` stays true for a followed link
+    /// to a directory so the UI can still navigate into it.
+    #[serde(default)]
+    pub kind: NodeKind,
+    /// Raw target of a symlink, as returned by readlink.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub link_target: Option<String>,
     pub children: Option<Vec<FileNode>>,
     pub last_modified: u64,
     pub file_count: u64,
+    /// Inode number on Unix; used with `last_modified` by incremental
+    /// rescans to tell which directories' entries changed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub inode: Option<u64>,
+    /// Per-extension/category totals for directories, when requested via
+    /// `ScanOptions::file_types`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub file_types: Option<FileTypeBreakdown>,
+    /// Owning user id (Unix).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub uid: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub gid: Option<u32>,
+    /// Permission bits (`st_mode & 0o7777`), without the file type.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mode: Option<u32>,
+    /// Bytes/file counts per owning uid for directories, when requested via
+    /// `ScanOptions::owners`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub owners: Option<OwnerBreakdown>,
+    /// Which measure `size` holds. Set on nodes returned from a scan command.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub size_mode: Option<SizeMode>,
+    /// Paths the scan could not read. Set on the root of a scan result only.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub scan_errors: Option<ScanErrorLog>,
+    /// Filesystem details of a volume in the drive list.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub drive: Option<DriveInfo>,
+    /// Fingerprint of every directory (path, mtime, inode) in a deep
+    /// aggregate's subtree, for incremental rescans. Opaque; only compared
+    /// with `subtree_stamp`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tree_stamp: Option<u64>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum NodeKind {
+    #[default]
+    File,
+    Directory,
+    Symlink,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ScanErrorKind {
+    PermissionDenied,
+    /// Removed between being listed and being stat'ed.
+    NotFound,
+    /// Symlink loop found by the walker.
+    Loop,
+    Other,
+}
+
+impl ScanErrorKind {
+    fn of(err: &std::io::Error) -> Self {
+        match err.kind() {
+            std::io::ErrorKind::PermissionDenied => ScanErrorKind::PermissionDenied,
+            std::io::ErrorKind::NotFound => ScanErrorKind::NotFound,
+            _ => ScanErrorKind::Other,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct ScanError {
+    pub path: String,
+    pub kind: ScanErrorKind,
+    pub message: String,
+}
+
+/// The first `MAX_REPORTED_ERRORS` failures of a scan plus the total count,
+/// so a scan of `/` as a normal user doesn't return thousands of entries.
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+pub struct ScanErrorLog {
+    pub errors: Vec<ScanError>,
+    pub total: u64,
 }
 
+const MAX_REPORTED_ERRORS: usize = 500;
+
 pub struct ScanStats {
     pub scanned_files: AtomicU64,
     pub total_size: AtomicU64,
     pub errors: AtomicU64,
+    /// Directory most recently entered by any worker, for progress display.
+    pub current_dir: std::sync::Mutex<String>,
 }
 
-pub fn scan_directory(
-    path: &str,
-    stats: Option<Arc<ScanStats>>,
-    cancel: Option<Arc<AtomicBool>>
-) -> Result<FileNode, String> {
-    let root_path = std::path::Path::new(path);
-    if !root_path.exists() {
-        return Err("Directory does not exist".to_string());
+impl ScanStats {
+    pub fn new() -> Self {
+        Self {
+            scanned_files: AtomicU64::new(0),
+            total_size: AtomicU64::new(0),
+            errors: AtomicU64::new(0),
+            current_dir: std::sync::Mutex::new(String::new()),
+        }
     }
 
-    if let Some(c) = &cancel {
-        if c.load(Ordering::Relaxed) {
-             return Err("Cancelled".to_string());
+    /// Best effort: skipped when another worker holds the lock, since any
+    /// recent directory will do.
+    pub fn set_current_dir(&self, path: &std::path::Path) {
+        if let Ok(mut current) = self.current_dir.try_lock() {
+            current.clear();
+            current.push_str(&path.to_string_lossy());
         }
     }
+}
 
-    // 1. List immediate children of the requested path
-    let read_dir = std::fs::read_dir(path).map_err(|e| e.to_string())?;
-    let entries: Vec<_> = read_dir.filter_map(|e| e.ok()).collect();
-    
-    // Partition
-    let mut files = Vec::new();
-    let mut dirs = Vec::new();
-    
-    for entry in entries {
-        if let Some(c) = &cancel {
-            if c.load(Ordering::Relaxed) { return Err("Cancelled".to_string()); }
+impl Default for ScanStats {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Caller-selectable scan behaviour. Part of the cache key: a cached tree is
+/// only reused for a request with identical options.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(default)]
+pub struct ScanOptions {
+    /// Aggregate bytes/counts per extension and category on every directory.
+    pub file_types: bool,
+    /// Aggregate bytes/counts per owning user on every directory.
+    pub owners: bool,
+    /// Which measure `FileNode::size` (and so every UI total) uses.
+    pub size_mode: SizeMode,
+    /// Don't descend into directories on a different device than the root,
+    /// nor into mount points below it (which catches same-device bind mounts).
+    pub one_file_system: bool,
+    /// Skip mount points of pseudo filesystems (/proc, /sys, cgroup, ...).
+    pub skip_virtual_fs: bool,
+    /// Glob patterns to leave out; see scan_filter.rs for matching rules.
+    pub exclude: Vec<String>,
+    pub symlinks: SymlinkPolicy,
+    /// Levels below the root that get a children list. Deeper directories
+    /// are sized as aggregates and can be opened later with `expand_dir`.
+    pub depth: u32,
+}
+
+impl Default for ScanOptions {
+    fn default() -> Self {
+        Self {
+            file_types: false,
+            owners: false,
+            size_mode: SizeMode::default(),
+            one_file_system: false,
+            skip_virtual_fs: true,
+            exclude: Vec::new(),
+            symlinks: SymlinkPolicy::default(),
+            depth: 1,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum SizeMode {
+    #[default]
+    Apparent,
+    Allocated,
+}
+
+/// What the scanner does with symbolic links.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum SymlinkPolicy {
+    /// Leave links out of the tree entirely.
+    Ignore,
+    /// List links as `NodeKind::Symlink` leaves sized by the link itself;
+    /// targets are never counted.
+    #[default]
+    Report,
+    /// Count link targets as if they were in place. Every directory is
+    /// counted once: a link to one already visited (an ancestor, a sibling,
+    /// or a target reached earlier) is reported instead of followed, which
+    /// also breaks cycles. A file reached both directly and through a link
+    /// to the file itself is counted twice, as with `du -L`.
+    Follow,
+}
+
+// Sizes of a single file after hard-link de-duplication.
+#[derive(Debug, Clone, Copy, Default)]
+struct FileSizes {
+    apparent: u64,
+    allocated: u64,
+}
+
+impl FileSizes {
+    fn of(meta: &std::fs::Metadata, links: &HardLinks) -> Self {
+        if !links.first_sighting(meta) {
+            return Self::default();
+        }
+        Self { apparent: meta.len(), allocated: allocated_bytes(meta) }
+    }
+
+    fn for_mode(&self, mode: SizeMode) -> u64 {
+        match mode {
+            SizeMode::Apparent => self.apparent,
+            SizeMode::Allocated => self.allocated,
         }
+    }
+}
+
+// Set of (dev, inode) pairs shared across the rayon workers of one walk.
+#[derive(Default)]
+struct InodeSet(std::sync::Mutex<std::collections::HashSet<(u64, u64)>>);
+
+impl InodeSet {
+    /// True the first time a given file is seen (always true off Unix).
+    #[cfg(unix)]
+    fn insert(&self, meta: &std::fs::Metadata) -> bool {
+        use std::os::unix::fs::MetadataExt;
+        self.0.lock().map(|mut seen| seen.insert((meta.dev(), meta.ino()))).unwrap_or(true)
+    }
+
+    #[cfg(not(unix))]
+    fn insert(&self, _meta: &std::fs::Metadata) -> bool {
+        true
+    }
+}
+
+/// (dev, inode) pairs of multiply-linked files already counted during one
+/// walk. Only the first link found is sized; the others count as zero bytes,
+/// like `du`. Which link wins depends on traversal order, and links reached
+/// through separate walks (watcher updates, reused incremental subtrees) are
+/// not de-duplicated against each other.
+#[derive(Default)]
+pub struct HardLinks(InodeSet);
+
+impl HardLinks {
+    /// False for a file already counted through another link.
+    #[cfg(unix)]
+    pub fn first_sighting(&self, meta: &std::fs::Metadata) -> bool {
+        use std::os::unix::fs::MetadataExt;
+        if meta.nlink() <= 1 || meta.is_dir() {
+            return true;
+        }
+        self.0.insert(meta)
+    }
+
+    #[cfg(not(unix))]
+    pub fn first_sighting(&self, _meta: &std::fs::Metadata) -> bool {
+        true
+    }
+}
+
+#[cfg(unix)]
+fn allocated_bytes(meta: &std::fs::Metadata) -> u64 {
+    use std::os::unix::fs::MetadataExt;
+    meta.blocks() * 512
+}
 
-        if let Ok(metadata) = entry.metadata() {
-            if metadata.is_dir() {
-                dirs.push(entry);
-            } else {
-                files.push((entry, metadata));
+#[cfg(not(unix))]
+fn allocated_bytes(meta: &std::fs::Metadata) -> u64 {
+    meta.len()
+}
+
+// Running totals for a directory while it is being measured.
+#[derive(Debug, Clone, Default)]
+struct DirTotals {
+    mode: SizeMode,
+    size: u64,
+    apparent: u64,
+    allocated: u64,
+    count: u64,
+    file_types: Option<FileTypeBreakdown>,
+    owners: Option<OwnerBreakdown>,
+}
+
+impl DirTotals {
+    fn new(options: &ScanOptions) -> Self {
+        Self {
+            mode: options.size_mode,
+            file_types: options.file_types.then(FileTypeBreakdown::default),
+            owners: options.owners.then(OwnerBreakdown::default),
+            ..Default::default()
+        }
+    }
+
+    fn with_breakdowns() -> Self {
+        Self {
+            file_types: Some(FileTypeBreakdown::default()),
+            owners: Some(OwnerBreakdown::default()),
+            ..Default::default()
+        }
+    }
+
+    fn add_file(&mut self, path: &std::path::Path, sizes: FileSizes, uid: Option<u32>) {
+        let size = sizes.for_mode(self.mode);
+        self.size += size;
+        self.apparent += sizes.apparent;
+        self.allocated += sizes.allocated;
+        self.count += 1;
+        if let Some(ft) = &mut self.file_types {
+            ft.add_file(path, size);
+        }
+        if let (Some(owners), Some(uid)) = (&mut self.owners, uid) {
+            owners.add_file(uid, size);
+        }
+    }
+
+    // `node.size` is already resolved for the scan's mode, so it is taken
+    // as-is rather than re-derived from the apparent/allocated pair.
+    fn add_node(&mut self, node: &FileNode) {
+        self.size += node.size;
+        self.apparent += node.apparent_size;
+        self.allocated += node.allocated_size;
+        if !node.is_dir {
+            self.count += 1;
+            if let Some(ft) = &mut self.file_types {
+                ft.add_file(std::path::Path::new(&node.path), node.size);
             }
+            if let (Some(owners), Some(uid)) = (&mut self.owners, node.uid) {
+                owners.add_file(uid, node.size);
+            }
+            return;
+        }
+        self.count += node.file_count;
+        if let (Some(ft), Some(child)) = (&mut self.file_types, &node.file_types) {
+            ft.merge(child);
+        }
+        if let (Some(owners), Some(child)) = (&mut self.owners, &node.owners) {
+            owners.merge(child);
         }
     }
-    
-    let mut total_size = 0;
-    let mut file_count = 0;
-    
-    // Files in root
-    for (_entry, meta) in &files {
-        let size = meta.len();
-        total_size += size;
-        file_count += 1;
-        
-        if let Some(s) = &stats {
-            s.scanned_files.fetch_add(1, Ordering::Relaxed);
-            s.total_size.fetch_add(size, Ordering::Relaxed);
+
+    fn add_totals(&mut self, other: &DirTotals) {
+        self.size += other.size;
+        self.apparent += other.apparent;
+        self.allocated += other.allocated;
+        self.count += other.count;
+        if let (Some(ft), Some(child)) = (&mut self.file_types, &other.file_types) {
+            ft.merge(child);
+        }
+        if let (Some(owners), Some(child)) = (&mut self.owners, &other.owners) {
+            owners.merge(child);
         }
     }
-    
-    // 2. Process subdirectories in parallel (Lookahead scan)
-    // We want to return a node for each directory that INCLUDES its own children list
-    // This allows the caller to cache these nodes effectively.
-    let dir_results_res: Result<Vec<FileNode>, String> = dirs.par_iter().map(|entry| {
-        if let Some(c) = &cancel {
-             if c.load(Ordering::Relaxed) { return Err("Cancelled".to_string()); }
-        }
-
-        let path = entry.path();
-        let path_str = path.to_string_lossy().to_string();
-        let name = entry.file_name().to_string_lossy().to_string();
-        
-        let metadata = entry.metadata().unwrap();
-        let modified = metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH)
-            .duration_since(SystemTime::UNIX_EPOCH).unwrap_or_default().as_secs();
-
-        // LOOKAHEAD: Scan the children of this subdirectory 
-        // to populate its `children` field and calculate exact size.
-        let (size, count, children) = scan_subdir_details(&path, stats.clone(), cancel.clone())?;
-
-        Ok(FileNode {
-            name,
-            path: path_str,
-            size,
-            is_dir: true,
-            children: Some(children), // We now populate this!
-            last_modified: modified,
-            file_count: count,
-        })
-    }).collect();
-    
-    let dir_results = dir_results_res?;
-    
-    // Aggregate totals
-    for dir in &dir_results {
-        total_size += dir.size;
-        file_count += dir.file_count;
-    }
-
-    // Convert files in root to FileNodes
-    let mut file_nodes: Vec<FileNode> = files.iter().map(|(entry, meta)| {
-        let name = entry.file_name().to_string_lossy().to_string();
-        let path_str = entry.path().to_string_lossy().to_string();
-        let modified = meta.modified().unwrap_or(SystemTime::UNIX_EPOCH)
-            .duration_since(SystemTime::UNIX_EPOCH).unwrap_or_default().as_secs();
-
-        FileNode {
-            name,
-            path: path_str,
-            size: meta.len(),
-            is_dir: false,
-            children: None,
-            last_modified: modified,
-            file_count: 1,
+}
+
+/// Totals of the directories just below the lookahead, recorded by the deep
+/// walks so `expand_dir` can give a collapsed directory a children list
+/// without walking it again. Only the top `RECORDED_DEPTH` levels of each
+/// walk are kept, up to `MAX_RECORDED_DIRS` in all; a re-walk replaces its
+/// subtree's entries and removed directories are dropped. Entries are used
+/// only while their `subtree_stamp` matches, which, like the incremental
+/// rescan, misses files rewritten in place. Kept in memory only.
+#[derive(Default)]
+pub struct DirSizes(std::sync::Mutex<std::collections::BTreeMap<std::path::PathBuf, SizedDir>>);
+
+const RECORDED_DEPTH: usize = 3;
+const MAX_RECORDED_DIRS: usize = 100_000;
+
+#[derive(Clone)]
+struct SizedDir {
+    totals: DirTotals,
+    stamp: u64,
+}
+
+impl DirSizes {
+    fn get(&self, entry: &Entry, ctx: &ScanContext) -> Option<(DirTotals, u64)> {
+        // Cloned so the lock isn't held across the stamp walk.
+        let dir = self.0.lock().ok()?.get(&entry.path)?.clone();
+        (subtree_stamp(&entry.path, ctx) == Some(dir.stamp)).then_some((dir.totals, dir.stamp))
+    }
+
+    /// Replace everything recorded at and below `root` with `dirs`.
+    fn replace_tree(&self, root: &std::path::Path, dirs: Vec<(std::path::PathBuf, SizedDir)>) {
+        if let Ok(mut sizes) = self.0.lock() {
+            remove_below(&mut sizes, root);
+            for (path, dir) in dirs {
+                if sizes.len() >= MAX_RECORDED_DIRS {
+                    break;
+                }
+                sizes.insert(path, dir);
+            }
         }
-    }).collect();
-    
-    // Combine dirs and files
-    let mut children_nodes = dir_results;
-    children_nodes.append(&mut file_nodes);
-    
-    // Sort by size descending
-    children_nodes.sort_by(|a, b| b.size.cmp(&a.size));
-    
-    Ok(FileNode {
-        name: root_path.file_name().unwrap_or_default().to_string_lossy().to_string(),
-        path: path.to_string(), // Keep original path string for consistency
-        size: total_size,
+    }
+
+    /// Forget `root` and everything below it.
+    fn remove_tree(&self, root: &std::path::Path) {
+        if let Ok(mut sizes) = self.0.lock() {
+            remove_below(&mut sizes, root);
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.lock().map(|s| s.len()).unwrap_or(0)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+// Paths order component-wise, so a directory's descendants directly follow it.
+fn remove_below(sizes: &mut std::collections::BTreeMap<std::path::PathBuf, SizedDir>, root: &std::path::Path) {
+    let stale: Vec<std::path::PathBuf> = sizes
+        .range::<std::path::Path, _>((std::ops::Bound::Included(root), std::ops::Bound::Unbounded))
+        .map(|(path, _)| path)
+        .take_while(|path| path.starts_with(root))
+        .cloned()
+        .collect();
+    for path in stale {
+        sizes.remove(&path);
+    }
+}
+
+// A directory entry the scan will count. `meta` is the link target's
+// metadata for followed symlinks and the entry's own otherwise.
+struct Entry {
+    path: std::path::PathBuf,
+    meta: std::fs::Metadata,
+    link_target: Option<String>,
+}
+
+impl Entry {
+    fn name(&self) -> String {
+        self.path.file_name().unwrap_or_default().to_string_lossy().to_string()
+    }
+
+    fn mark_link(&self, node: &mut FileNode) {
+        if self.link_target.is_some() {
+            node.kind = NodeKind::Symlink;
+            node.link_target = self.link_target.clone();
+        }
+    }
+}
+
+// Leaf node for a non-directory entry.
+fn file_node(entry: &Entry, sizes: FileSizes, mode: SizeMode) -> FileNode {
+    let (uid, gid, permissions) = ownership_of(&entry.meta);
+    let mut node = FileNode {
+        name: entry.name(),
+        path: entry.path.to_string_lossy().to_string(),
+        size: sizes.for_mode(mode),
+        apparent_size: sizes.apparent,
+        allocated_size: sizes.allocated,
+        is_dir: false,
+        kind: NodeKind::File,
+        link_target: None,
+        children: None,
+        last_modified: modified_secs(&entry.meta),
+        file_count: 1,
+        inode: inode_of(&entry.meta),
+        file_types: None,
+        uid,
+        gid,
+        mode: permissions,
+        owners: None,
+        size_mode: None,
+        scan_errors: None,
+        drive: None,
+        tree_stamp: None,
+    };
+    entry.mark_link(&mut node);
+    node
+}
+
+// Directory node from measured totals.
+fn dir_node(entry: &Entry, totals: DirTotals, children: Option<Vec<FileNode>>) -> FileNode {
+    let (uid, gid, mode) = ownership_of(&entry.meta);
+    let mut node = FileNode {
+        name: entry.name(),
+        path: entry.path.to_string_lossy().to_string(),
+        size: totals.size,
+        apparent_size: totals.apparent,
+        allocated_size: totals.allocated,
         is_dir: true,
-        children: Some(children_nodes),
-        last_modified: 0,
-        file_count,
-    })
+        kind: NodeKind::Directory,
+        link_target: None,
+        children,
+        last_modified: modified_secs(&entry.meta),
+        file_count: totals.count,
+        inode: inode_of(&entry.meta),
+        file_types: totals.file_types,
+        uid,
+        gid,
+        mode,
+        owners: totals.owners,
+        size_mode: None,
+        scan_errors: None,
+        drive: None,
+        tree_stamp: None,
+    };
+    entry.mark_link(&mut node);
+    node
 }
 
-// Scans a subdirectory: Lists ITS children, and calculates their sizes (deep)
-fn scan_subdir_details(
-    path: &std::path::Path, 
-    stats: Option<Arc<ScanStats>>, 
-    cancel: Option<Arc<AtomicBool>>
-) -> Result<(u64, u64, Vec<FileNode>), String> {
-    // List children of this subdirectory
-    
-    let mut total_size = 0;
-    let mut total_count = 0;
-    let mut children_nodes = Vec::new();
-
-    if let Ok(read_dir) = std::fs::read_dir(path) {
-        let entries: Vec<_> = read_dir.filter_map(|e| e.ok()).collect();
-
-        // Split into files/dirs
-        let mut sub_files_size = 0;
-        let mut sub_files_count = 0;
-        let mut sub_dirs = Vec::new();
-        let mut sub_files: Vec<(std::fs::DirEntry, std::fs::Metadata)> = Vec::new();
-
-        for entry in entries {
-            if let Some(c) = &cancel {
-                 if c.load(Ordering::Relaxed) { return Err("Cancelled".to_string()); }
+// Directory node for a deep aggregate: totals without a children list.
+fn deep_dir_node(entry: &Entry, (totals, stamp): (DirTotals, u64)) -> FileNode {
+    FileNode { tree_stamp: Some(stamp), ..dir_node(entry, totals, None) }
+}
+
+// Per-scan state shared by every level of one walk.
+struct ScanContext {
+    options: ScanOptions,
+    filter: ScanFilter,
+    links: HardLinks,
+    /// Directories entered so far; only tracked under `SymlinkPolicy::Follow`.
+    visited_dirs: InodeSet,
+    stats: Option<Arc<ScanStats>>,
+    cancel: Option<Arc<AtomicBool>>,
+    errors: std::sync::Mutex<ScanErrorLog>,
+    /// Filled by the deep walks and consulted before starting one.
+    dir_sizes: Arc<DirSizes>,
+}
+
+impl ScanContext {
+    fn new(
+        root: &std::path::Path,
+        options: &ScanOptions,
+        stats: Option<Arc<ScanStats>>,
+        cancel: Option<Arc<AtomicBool>>,
+    ) -> Result<Self, String> {
+        let ctx = Self {
+            options: options.clone(),
+            filter: ScanFilter::new(root, options)?,
+            links: HardLinks::default(),
+            visited_dirs: InodeSet::default(),
+            stats,
+            cancel,
+            errors: std::sync::Mutex::new(ScanErrorLog::default()),
+            dir_sizes: Arc::default(),
+        };
+        if let Ok(meta) = std::fs::metadata(root) {
+            ctx.enter_dir(&meta);
+        }
+        Ok(ctx)
+    }
+
+    fn follows_links(&self) -> bool {
+        self.options.symlinks == SymlinkPolicy::Follow
+    }
+
+    // Registers a directory for cycle detection; false if already visited.
+    fn enter_dir(&self, meta: &std::fs::Metadata) -> bool {
+        !self.follows_links() || self.visited_dirs.insert(meta)
+    }
+
+    fn is_cancelled(&self) -> bool {
+        self.cancel.as_ref().map(|c| c.load(Ordering::Relaxed)).unwrap_or(false)
+    }
+
+    fn record_file(&self, sizes: FileSizes) {
+        if let Some(st) = &self.stats {
+            st.scanned_files.fetch_add(1, Ordering::Relaxed);
+            st.total_size.fetch_add(sizes.for_mode(self.options.size_mode), Ordering::Relaxed);
+        }
+    }
+
+    fn record_error(&self, path: &std::path::Path, kind: ScanErrorKind, message: String) {
+        if let Some(st) = &self.stats {
+            st.errors.fetch_add(1, Ordering::Relaxed);
+        }
+        if let Ok(mut log) = self.errors.lock() {
+            log.total += 1;
+            if log.errors.len() < MAX_REPORTED_ERRORS {
+                log.errors.push(ScanError { path: path.to_string_lossy().to_string(), kind, message });
             }
+        }
+    }
 
-             if let Ok(meta) = entry.metadata() {
-                if meta.is_dir() {
-                    sub_dirs.push(entry);
-                } else {
-                    let s = meta.len();
-                    sub_files_size += s;
-                    sub_files_count += 1;
-
-                    if let Some(st) = &stats {
-                        st.scanned_files.fetch_add(1, Ordering::Relaxed);
-                        st.total_size.fetch_add(s, Ordering::Relaxed);
-                    }
+    fn record_io_error(&self, path: &std::path::Path, err: &std::io::Error) {
+        self.record_error(path, ScanErrorKind::of(err), err.to_string());
+    }
 
-                    sub_files.push((entry, meta));
+    fn record_walk_error(&self, err: &walkdir::Error) {
+        let kind = if err.loop_ancestor().is_some() {
+            ScanErrorKind::Loop
+        } else {
+            err.io_error().map(ScanErrorKind::of).unwrap_or(ScanErrorKind::Other)
+        };
+        let path = err.path().unwrap_or(std::path::Path::new(""));
+        self.record_error(path, kind, err.to_string());
+    }
+
+    // None when nothing failed, so clean scans serialize without the field.
+    fn take_errors(&self) -> Option<ScanErrorLog> {
+        let log = std::mem::take(&mut *self.errors.lock().ok()?);
+        (log.total > 0).then_some(log)
+    }
+
+    // Applies the filter and symlink policy to one entry given its own
+    // (lstat) metadata. None if the scan should leave it out.
+    fn classify(&self, path: std::path::PathBuf, meta: std::fs::Metadata) -> Option<Entry> {
+        if !meta.file_type().is_symlink() {
+            // Under Follow a directory already counted through a link is
+            // left out, so every directory is counted once.
+            if !self.filter.allows(&path, &meta) || (meta.is_dir() && !self.enter_dir(&meta)) {
+                return None;
+            }
+            return Some(Entry { path, meta, link_target: None });
+        }
+
+        let link_target = std::fs::read_link(&path)
+            .map(|t| t.to_string_lossy().to_string())
+            .unwrap_or_default();
+        match self.options.symlinks {
+            SymlinkPolicy::Ignore => None,
+            SymlinkPolicy::Report => self.filter.allows(&path, &meta)
+                .then_some(Entry { path, meta, link_target: Some(link_target) }),
+            SymlinkPolicy::Follow => {
+                let target = std::fs::metadata(&path).ok()
+                    .filter(|t| self.filter.allows(&path, t))
+                    .filter(|t| !t.is_dir() || self.enter_dir(t));
+                // Broken, filtered-out and cyclic links are reported as-is.
+                let meta = target.unwrap_or(meta);
+                self.filter.allows(&path, &meta)
+                    .then_some(Entry { path, meta, link_target: Some(link_target) })
+            }
+        }
+    }
+
+    // Entries of `path` the scan counts, split into directories and files.
+    fn list(&self, path: &std::path::Path) -> Result<Listing, String> {
+        if let Some(st) = &self.stats {
+            st.set_current_dir(path);
+        }
+        let read_dir = std::fs::read_dir(path).map_err(|e| {
+            self.record_io_error(path, &e);
+            e.to_string()
+        })?;
+        let mut entries = Vec::new();
+        for entry in read_dir {
+            if self.is_cancelled() { return Err("Cancelled".to_string()); }
+
+            let entry = match entry {
+                Ok(e) => e,
+                Err(e) => {
+                    self.record_io_error(path, &e);
+                    continue;
                 }
-             }
-        }
-
-        total_size += sub_files_size;
-        total_count += sub_files_count;
-
-        // Process these subdirectories (Deep scan for size)
-        let sub_dir_nodes_res: Result<Vec<FileNode>, String> = sub_dirs.par_iter().map(|entry| {
-             if let Some(c) = &cancel {
-                 if c.load(Ordering::Relaxed) { return Err("Cancelled".to_string()); }
-             }
-
-             let p = entry.path();
-             let name = entry.file_name().to_string_lossy().to_string();
-             let p_str = p.to_string_lossy().to_string();
-
-             // Get stats using walkdir (Deep scan)
-             let (s, c) = get_deep_stats(&p, stats.clone(), cancel.clone())?;
-
-             let m = entry.metadata().ok().and_then(|m| m.modified().ok())
-                .and_then(|t| t.duration_since(SystemTime::UNIX_EPOCH).ok())
-                .map(|d| d.as_secs()).unwrap_or(0);
-
-             Ok(FileNode {
-                 name,
-                 path: p_str,
-                 size: s,
-                 is_dir: true,
-                 children: None, // We stop lookahead at 1 level deep to avoid recursion explosion
-                 last_modified: m,
-                 file_count: c,
-             })
-        }).collect();
-
-        let sub_dir_nodes = sub_dir_nodes_res?;
-
-        for node in &sub_dir_nodes {
-            total_size += node.size;
-            total_count += node.file_count;
-        }
-
-        children_nodes = sub_dir_nodes;
-
-        // Include file children so the cached lookahead matches a direct scan.
-        // Without this, navigating into a leaf folder (files only) returns
-        // an empty list until the user hits refresh.
-        let file_nodes: Vec<FileNode> = sub_files.iter().map(|(entry, meta)| {
-            let name = entry.file_name().to_string_lossy().to_string();
-            let p_str = entry.path().to_string_lossy().to_string();
-            let m = meta.modified().ok()
-                .and_then(|t| t.duration_since(SystemTime::UNIX_EPOCH).ok())
-                .map(|d| d.as_secs())
-                .unwrap_or(0);
-            FileNode {
-                name,
-                path: p_str,
-                size: meta.len(),
-                is_dir: false,
-                children: None,
-                last_modified: m,
-                file_count: 1,
+            };
+            // The entry may vanish between readdir and stat; skip it.
+            match entry.metadata() {
+                Ok(meta) => entries.push((entry.path(), meta)),
+                Err(e) => self.record_io_error(&entry.path(), &e),
+            }
+        }
+
+        // Real directories claim their inodes before links are followed, so
+        // a link to a sibling is the one reported, whatever readdir's order.
+        entries.sort_by_key(|(_, meta)| meta.file_type().is_symlink());
+        let mut listing = Listing::default();
+        for (entry_path, meta) in entries {
+            match self.classify(entry_path, meta) {
+                Some(e) if e.meta.is_dir() => listing.dirs.push(e),
+                Some(e) => listing.files.push(e),
+                None => {}
             }
-        }).collect();
-        children_nodes.extend(file_nodes);
+        }
+        Ok(listing)
+    }
+}
+
+#[derive(Default)]
+struct Listing {
+    dirs: Vec<Entry>,
+    files: Vec<Entry>,
+}
 
-        children_nodes.sort_by(|a, b| b.size.cmp(&a.size));
+pub fn scan_directory(
+    path: &str,
+    options: &ScanOptions,
+    stats: Option<Arc<ScanStats>>,
+    cancel: Option<Arc<AtomicBool>>
+) -> Result<FileNode, String> {
+    scan_directory_with_sizes(path, options, Arc::default(), stats, cancel)
+}
+
+/// `scan_directory` that also records the totals of every directory below
+/// the lookahead into `dir_sizes`, for later `expand_dir` calls.
+pub fn scan_directory_with_sizes(
+    path: &str,
+    options: &ScanOptions,
+    dir_sizes: Arc<DirSizes>,
+    stats: Option<Arc<ScanStats>>,
+    cancel: Option<Arc<AtomicBool>>
+) -> Result<FileNode, String> {
+    let root_path = std::path::Path::new(path);
+    // Root mtime/inode for incremental rescans. Taken before the walk so
+    // changes made during it show up as changes next time.
+    let root_meta = std::fs::metadata(root_path)
+        .map_err(|_| "Directory does not exist".to_string())?;
+
+    let ctx = ScanContext { dir_sizes, ..ScanContext::new(root_path, options, stats, cancel)? };
+    if ctx.is_cancelled() {
+        return Err("Cancelled".to_string());
     }
 
-    Ok((total_size, total_count, children_nodes))
+    // Subdirectories down to `depth` levels get their own children list so
+    // the caller can cache them and navigate without rescanning.
+    let (totals, children) = scan_listing(root_path, options.depth, &ctx)?;
+    let root = Entry { path: root_path.to_path_buf(), meta: root_meta, link_target: None };
+
+    Ok(FileNode {
+        path: path.to_string(), // Keep original path string for consistency
+        size_mode: Some(options.size_mode),
+        scan_errors: ctx.take_errors(),
+        drive: None,
+        ..dir_node(&root, totals, Some(children))
+    })
 }
 
+// Lists `path` and measures its entries. Subdirectories get their own
+// children list while `lookahead > 0`; below that they are deep aggregates.
+fn scan_listing(
+    path: &std::path::Path,
+    lookahead: u32,
+    ctx: &ScanContext,
+) -> Result<(DirTotals, Vec<FileNode>), String> {
+    let Listing { dirs, files } = ctx.list(path)?;
+
+    let mut totals = DirTotals::new(&ctx.options);
+    let mut file_nodes = Vec::with_capacity(files.len());
+    for entry in &files {
+        let sizes = FileSizes::of(&entry.meta, &ctx.links);
+        totals.add_file(&entry.path, sizes, ownership_of(&entry.meta).0);
+        ctx.record_file(sizes);
+        file_nodes.push(file_node(entry, sizes, ctx.options.size_mode));
+    }
+
+    let dir_nodes: Result<Vec<FileNode>, String> = dirs.par_iter().map(|entry| {
+        if ctx.is_cancelled() { return Err("Cancelled".to_string()); }
+
+        if lookahead == 0 {
+            let deep = match ctx.dir_sizes.get(entry, ctx) {
+                Some(known) => known,
+                None => get_deep_stats(&entry.path, ctx)?,
+            };
+            return Ok(deep_dir_node(entry, deep));
+        }
+        let (sub_totals, children) = match scan_listing(&entry.path, lookahead - 1, ctx) {
+            Ok(listed) => listed,
+            // Unreadable directory: already in the error log, keep it empty.
+            Err(_) if !ctx.is_cancelled() => (DirTotals::new(&ctx.options), Vec::new()),
+            Err(e) => return Err(e),
+        };
+        Ok(dir_node(entry, sub_totals, Some(children)))
+    }).collect();
+    let mut children = dir_nodes?;
+
+    for node in &children {
+        totals.add_node(node);
+    }
+    // Include file children so the cached lookahead matches a direct scan.
+    // Without this, navigating into a leaf folder (files only) returns
+    // an empty list until the user hits refresh.
+    children.extend(file_nodes);
+    children.sort_by_key(|c| std::cmp::Reverse(c.size));
+
+    Ok((totals, children))
+}
+
+/// Give the collapsed directory `path` a children list. Subdirectories are
+/// sized from `dir_sizes` where the recorded totals are still current and
+/// walked otherwise (which records them for next time); files are stat'ed.
+/// Hard links are only de-duplicated within this listing. None if `path` is
+/// gone, not a directory, or left out by `options`.
+pub fn expand_dir(
+    path: &std::path::Path,
+    options: &ScanOptions,
+    dir_sizes: Arc<DirSizes>,
+) -> Option<FileNode> {
+    let meta = std::fs::symlink_metadata(path).ok()?;
+    let ctx = ScanContext {
+        dir_sizes,
+        ..ScanContext::new(path.parent().unwrap_or(path), options, None, None).ok()?
+    };
+    let entry = ctx.classify(path.to_path_buf(), meta).filter(|e| e.meta.is_dir())?;
+    let (totals, children) = scan_listing(path, 0, &ctx).ok()?;
+    Some(dir_node(&entry, totals, Some(children)))
+}
+
+/// The node at `path` inside `tree`, if `tree` lists it.
+pub fn find_node<'a>(tree: &'a FileNode, path: &std::path::Path) -> Option<&'a FileNode> {
+    if std::path::Path::new(&tree.path) == path {
+        return Some(tree);
+    }
+    tree.children
+        .as_ref()?
+        .iter()
+        .find(|c| c.is_dir && path.starts_with(&c.path))
+        .and_then(|c| find_node(c, path))
+}
+
+// Deep totals of `path` and its `subtree_stamp`.
 fn get_deep_stats(
     path: &std::path::Path, 
-    stats: Option<Arc<ScanStats>>, 
-    cancel: Option<Arc<AtomicBool>>
-) -> Result<(u64, u64), String> {
-    let mut size = 0;
-    let mut count = 0;
+    ctx: &ScanContext,
+) -> Result<(DirTotals, u64), String> {
+    // Directories still being walked, outermost first: walkdir yields a
+    // directory before its contents, so an entry at depth d closes every
+    // open directory at depth >= d. Closed ones down to RECORDED_DEPTH are
+    // recorded in `dir_sizes`.
+    let root_stamp = std::fs::metadata(path).map(|meta| dir_stamp(path, &meta)).unwrap_or(0);
+    let mut open: Vec<(std::path::PathBuf, SizedDir)> = vec![(
+        path.to_path_buf(),
+        SizedDir { totals: DirTotals::new(&ctx.options), stamp: root_stamp },
+    )];
+    let mut closed = Vec::new();
+    let close = |open: &mut Vec<(std::path::PathBuf, SizedDir)>, closed: &mut Vec<_>| {
+        if let Some((dir_path, dir)) = open.pop() {
+            if let Some((_, parent)) = open.last_mut() {
+                parent.totals.add_totals(&dir.totals);
+                parent.stamp = parent.stamp.wrapping_add(dir.stamp);
+            }
+            if open.len() <= RECORDED_DEPTH {
+                closed.push((dir_path, dir));
+            }
+        }
+    };
+
+    // Excluded entries, other filesystems, pseudo-fs mounts and already
+    // visited link targets are pruned here so walkdir never descends into
+    // them. With follow_links, walkdir reports the target's file type.
+    let walker = walkdir::WalkDir::new(path)
+        .min_depth(1)
+        .follow_links(ctx.follows_links())
+        .into_iter()
+        .filter_entry(|e| {
+            let meta = match e.metadata() {
+                Ok(m) => m,
+                Err(_) => return true,
+            };
+            if !ctx.filter.allows(e.path(), &meta) {
+                return false;
+            }
+            if !e.file_type().is_dir() {
+                return true;
+            }
+            // A directory reached a second time, through a link or directly
+            // after a link got there first, is a cycle or a duplicate.
+            ctx.enter_dir(&meta)
+        });
     
     // Using simple walkdir; we should periodically check cancel
-    for (idx, entry) in walkdir::WalkDir::new(path).min_depth(1).into_iter().enumerate() {
-        if idx % 100 == 0 {
-             if let Some(c) = &cancel {
-                 if c.load(Ordering::Relaxed) { return Err("Cancelled".to_string()); }
-             }
+    for (idx, entry) in walker.enumerate() {
+        if idx % 100 == 0 && ctx.is_cancelled() {
+            return Err("Cancelled".to_string());
         }
 
         match entry {
             Ok(entry) => {
-                if entry.file_type().is_file() {
-                    let s = entry.metadata().map(|m| m.len()).unwrap_or(0);
-                    size += s;
-                    count += 1;
-
-                    if let Some(st) = &stats {
-                        st.scanned_files.fetch_add(1, Ordering::Relaxed);
-                        st.total_size.fetch_add(s, Ordering::Relaxed);
+                while open.len() > entry.depth() {
+                    close(&mut open, &mut closed);
+                }
+                if entry.file_type().is_dir() {
+                    if let Some(st) = &ctx.stats {
+                        st.set_current_dir(entry.path());
                     }
+                    let stamp = entry.metadata().map(|m| dir_stamp(entry.path(), &m)).unwrap_or(0);
+                    open.push((entry.path().to_path_buf(), SizedDir { totals: DirTotals::new(&ctx.options), stamp }));
+                    continue;
                 }
-            }
-            Err(_e) => {
-                // Track permission denied and other errors
-                if let Some(st) = &stats {
-                    st.errors.fetch_add(1, Ordering::Relaxed);
+                let counted = entry.file_type().is_file()
+                    || (entry.file_type().is_symlink() && ctx.options.symlinks != SymlinkPolicy::Ignore);
+                if counted {
+                    match entry.metadata() {
+                        Ok(meta) => {
+                            let sizes = FileSizes::of(&meta, &ctx.links);
+                            if let Some((_, dir)) = open.last_mut() {
+                                dir.totals.add_file(entry.path(), sizes, ownership_of(&meta).0);
+                            }
+                            ctx.record_file(sizes);
+                        }
+                        Err(e) => ctx.record_walk_error(&e),
+                    }
                 }
             }
+            Err(e) => {
+                // Permission denied, vanished entries, link loops
+                ctx.record_walk_error(&e);
+            }
         }
     }
     
-    Ok((size, count))
+    while open.len() > 1 {
+        close(&mut open, &mut closed);
+    }
+    ctx.dir_sizes.replace_tree(path, closed);
+
+    Ok(open.pop().map(|(_, root)| (root.totals, root.stamp)).unwrap_or_default())
+}
+
+// One directory's contribution to a tree stamp. Its mtime changes whenever
+// an entry is added, removed or renamed in it. Hashed with the standard
+// library's default hasher, so stamps persisted by another build may not
+// match and just cause a re-walk.
+fn dir_stamp(path: &std::path::Path, meta: &std::fs::Metadata) -> u64 {
+    use std::hash::{Hash, Hasher};
+    let mut hasher = std::collections::hash_map::DefaultHasher::new();
+    (path, meta.modified().ok(), inode_of(meta)).hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Sum of `dir_stamp` over `path` and every directory below it that a deep
+/// walk visits, or None if part of the tree can't be read. Equal stamps
+/// mean no directory in the tree gained, lost or renamed an entry; costs a
+/// listing of each directory but no per-file stat.
+fn subtree_stamp(path: &std::path::Path, ctx: &ScanContext) -> Option<u64> {
+    let mut stamp = dir_stamp(path, &std::fs::metadata(path).ok()?);
+    let walker = walkdir::WalkDir::new(path)
+        .min_depth(1)
+        .follow_links(ctx.follows_links())
+        .into_iter()
+        .filter_entry(|e| {
+            e.file_type().is_dir() && e.metadata().map(|m| ctx.filter.allows(e.path(), &m)).unwrap_or(true)
+        });
+    for (idx, entry) in walker.enumerate() {
+        if idx % 100 == 0 && ctx.is_cancelled() {
+            return None;
+        }
+        let entry = entry.ok()?;
+        stamp = stamp.wrapping_add(entry.metadata().map(|m| dir_stamp(entry.path(), &m)).unwrap_or(0));
+    }
+    Some(stamp)
+}
+
+// ── Incremental rescan ─────────────────────────────────────────────────
+//
+// Re-lists the directories that carry a `children` list in the previous tree
+// (cheap), re-stats their files, and re-walks a deep subtree unless its
+// `subtree_stamp` still matches, i.e. no directory anywhere in it gained, lost
+// or renamed an entry. Caveat: a file rewritten in place (same name, new
+// size) doesn't change any directory mtime, so growth of an existing file
+// below the lookahead is only picked up by a full `refresh_scan`.
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum ChangeKind {
+    Added,
+    Removed,
+    Modified,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct NodeChange {
+    pub path: String,
+    pub kind: ChangeKind,
+    pub old_size: u64,
+    pub new_size: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct IncrementalScan {
+    pub node: FileNode,
+    /// Nodes added, removed or whose size/count/mtime differ from the previous tree.
+    pub changed: Vec<NodeChange>,
+    /// Deep subtrees carried over from the previous tree without re-walking.
+    pub reused_subtrees: u64,
+}
+
+struct IncrementalState {
+    ctx: ScanContext,
+    changed: std::sync::Mutex<Vec<NodeChange>>,
+    reused: AtomicU64,
+}
+
+impl IncrementalState {
+    fn record(&self, path: &str, kind: ChangeKind, old_size: u64, new_size: u64) {
+        if let Ok(mut changed) = self.changed.lock() {
+            changed.push(NodeChange { path: path.to_string(), kind, old_size, new_size });
+        }
+    }
+
+    fn record_if_modified(&self, previous: Option<&FileNode>, node: &FileNode) {
+        match previous {
+            None => self.record(&node.path, ChangeKind::Added, 0, node.size),
+            Some(prev) => {
+                if prev.size != node.size
+                    || prev.file_count != node.file_count
+                    || prev.last_modified != node.last_modified
+                {
+                    self.record(&node.path, ChangeKind::Modified, prev.size, node.size);
+                }
+            }
+        }
+    }
+}
+
+/// `dir_sizes` should be the previous scan's: reused subtrees keep their
+/// recorded totals and re-walked ones replace theirs.
+pub fn scan_directory_incremental(
+    previous: &FileNode,
+    options: &ScanOptions,
+    dir_sizes: Arc<DirSizes>,
+    stats: Option<Arc<ScanStats>>,
+    cancel: Option<Arc<AtomicBool>>
+) -> Result<IncrementalScan, String> {
+    let root_path = std::path::Path::new(&previous.path);
+    let root_meta = std::fs::metadata(root_path)
+        .ok()
+        .filter(|m| m.is_dir())
+        .ok_or_else(|| "Directory does not exist".to_string())?;
+
+    let state = IncrementalState {
+        ctx: ScanContext { dir_sizes, ..ScanContext::new(root_path, options, stats, cancel)? },
+        changed: std::sync::Mutex::new(Vec::new()),
+        reused: AtomicU64::new(0),
+    };
+
+    // Same shape as scan_directory: directories down to `options.depth`
+    // carry children lists, anything below that is a deep aggregate (which
+    // collapses directories expanded since the previous scan).
+    let prev_children = previous.children.as_deref().unwrap_or(&[]);
+    let (totals, children) = rescan_listing(root_path, prev_children, options.depth, &state)?;
+
+    let root = Entry { path: root_path.to_path_buf(), meta: root_meta, link_target: None };
+    let node = FileNode {
+        name: previous.name.clone(),
+        path: previous.path.clone(),
+        size_mode: Some(options.size_mode),
+        scan_errors: state.ctx.take_errors(),
+        drive: None,
+        ..dir_node(&root, totals, Some(children))
+    };
+    state.record_if_modified(Some(previous), &node);
+
+    let mut changed = state.changed.into_inner().map_err(|e| e.to_string())?;
+    changed.sort_by(|a, b| a.path.cmp(&b.path));
+
+    Ok(IncrementalScan {
+        node,
+        changed,
+        reused_subtrees: state.reused.load(Ordering::Relaxed),
+    })
+}
+
+// Lists `path` and rebuilds its child nodes. Subdirectories get their own
+// children list while `lookahead > 0`; below that they are deep aggregates.
+fn rescan_listing(
+    path: &std::path::Path,
+    prev_children: &[FileNode],
+    lookahead: u32,
+    state: &IncrementalState,
+) -> Result<(DirTotals, Vec<FileNode>), String> {
+    let ctx = &state.ctx;
+    let Listing { dirs, files } = ctx.list(path)?;
+
+    let prev_by_path: std::collections::HashMap<&str, &FileNode> =
+        prev_children.iter().map(|c| (c.path.as_str(), c)).collect();
+    let find_prev = |p: &str| prev_by_path.get(p).copied();
+
+    let dir_nodes: Result<Vec<FileNode>, String> = dirs.par_iter().map(|entry| {
+        if ctx.is_cancelled() { return Err("Cancelled".to_string()); }
+
+        let p = &entry.path;
+        let prev = find_prev(&p.to_string_lossy()).filter(|n| n.is_dir);
+
+        let node = if lookahead > 0 {
+            // Listings are cheap; recurse so deeper unchanged subtrees are reused.
+            let prev_grandchildren = prev.and_then(|n| n.children.as_deref()).unwrap_or(&[]);
+            let (totals, children) = match rescan_listing(p, prev_grandchildren, lookahead - 1, state) {
+                Ok(listed) => listed,
+                // Unreadable directory: already in the error log, keep it empty.
+                Err(_) if !ctx.is_cancelled() => (DirTotals::new(&ctx.options), Vec::new()),
+                Err(e) => return Err(e),
+            };
+            dir_node(entry, totals, Some(children))
+        } else {
+            let stamp = prev.and_then(|n| n.tree_stamp);
+            match prev.filter(|_| stamp.is_some() && stamp == subtree_stamp(p, ctx)) {
+                Some(prev) => {
+                    state.reused.fetch_add(1, Ordering::Relaxed);
+                    let mut reused = DirTotals::new(&ctx.options);
+                    reused.add_node(prev);
+                    FileNode { tree_stamp: stamp, ..dir_node(entry, reused, None) }
+                }
+                None => deep_dir_node(entry, get_deep_stats(p, ctx)?),
+            }
+        };
+
+        state.record_if_modified(prev, &node);
+        Ok(node)
+    }).collect();
+    let mut children = dir_nodes?;
+
+    for entry in &files {
+        let sizes = FileSizes::of(&entry.meta, &ctx.links);
+        let node = file_node(entry, sizes, ctx.options.size_mode);
+        ctx.record_file(sizes);
+        state.record_if_modified(find_prev(&node.path).filter(|n| !n.is_dir), &node);
+        children.push(node);
+    }
+
+    let current: std::collections::HashSet<&str> = children.iter().map(|c| c.path.as_str()).collect();
+    for prev in prev_children {
+        if !current.contains(prev.path.as_str()) {
+            state.record(&prev.path, ChangeKind::Removed, prev.size, 0);
+            if prev.is_dir {
+                ctx.dir_sizes.remove_tree(std::path::Path::new(&prev.path));
+            }
+        }
+    }
+
+    let mut totals = DirTotals::new(&ctx.options);
+    for child in &children {
+        totals.add_node(child);
+    }
+    children.sort_by_key(|c| std::cmp::Reverse(c.size));
+
+    Ok((totals, children))
+}
+
+// ── In-place tree updates ──────────────────────────────────────────────
+
+/// Measure a single path as a leaf node: files are stat'ed, directories get a
+/// deep size walk but no children list. None if the path is gone.
+pub fn measure_path(path: &std::path::Path, options: &ScanOptions) -> Option<FileNode> {
+    let meta = std::fs::symlink_metadata(path).ok()?;
+    let ctx = ScanContext::new(path.parent().unwrap_or(path), options, None, None).ok()?;
+    // Excluded or ignored paths never appear in the tree, so treat them as gone.
+    let entry = ctx.classify(path.to_path_buf(), meta)?;
+    if !entry.meta.is_dir() {
+        return Some(file_node(&entry, FileSizes::of(&entry.meta, &ctx.links), options.size_mode));
+    }
+    let deep = get_deep_stats(path, &ctx).ok()?;
+    Some(deep_dir_node(&entry, deep))
+}
+
+/// Where a change at `target` can be applied with `apply_node_update`:
+/// `target` itself when every directory above it carries a children list,
+/// else the deep aggregate on the way down, which has to be re-measured as a
+/// whole. None if `target` is outside `tree`.
+pub fn update_point(tree: &FileNode, target: &std::path::Path) -> Option<std::path::PathBuf> {
+    let relative = target.strip_prefix(&tree.path).ok()?;
+    let mut node = tree;
+    let mut path = std::path::PathBuf::from(&tree.path);
+    for component in relative.components() {
+        path.push(component);
+        let child = node.children.as_ref()?.iter().find(|c| std::path::Path::new(&c.path) == path);
+        match child {
+            Some(child) if child.children.is_some() => node = child,
+            _ => return Some(path),
+        }
+    }
+    Some(path)
+}
+
+/// Replace, insert (`Some`) or remove (`None`) the node at `target` inside
+/// `tree`, re-aggregating sizes, `file_count` and file-type totals up the
+/// ancestor chain. Only works where the parent of `target` carries a
+/// children list. Returns the (size, file_count) delta applied, or None if
+/// `target` isn't in the tree.
+pub fn apply_node_update(
+    tree: &mut FileNode,
+    target: &std::path::Path,
+    update: Option<&FileNode>,
+) -> Option<(i64, i64)> {
+    let parent = target.parent()?;
+    let listed_parent = find_node(tree, parent).filter(|n| n.children.is_some())?;
+    let old = listed_parent
+        .children
+        .as_ref()?
+        .iter()
+        .find(|c| std::path::Path::new(&c.path) == target)
+        .cloned();
+    if old.is_none() && update.is_none() {
+        return None;
+    }
+
+    // Track breakdowns unconditionally; ancestors without one ignore them.
+    let mut removed = DirTotals::with_breakdowns();
+    let mut added = DirTotals::with_breakdowns();
+    if let Some(node) = &old {
+        removed.add_node(node);
+    }
+    if let Some(node) = update {
+        added.add_node(node);
+    }
+    let delta = (
+        added.size as i64 - removed.size as i64,
+        added.count as i64 - removed.count as i64,
+    );
+    adjust_chain(tree, target, update, &delta, &removed, &added);
+    Some(delta)
+}
+
+fn adjust_chain(
+    node: &mut FileNode,
+    target: &std::path::Path,
+    update: Option<&FileNode>,
+    delta: &(i64, i64),
+    removed: &DirTotals,
+    added: &DirTotals,
+) {
+    let is_parent = target.parent() == Some(std::path::Path::new(&node.path));
+    if let Some(children) = node.children.as_mut() {
+        if is_parent {
+            let pos = children.iter().position(|c| std::path::Path::new(&c.path) == target);
+            match (pos, update) {
+                (Some(i), Some(n)) => children[i] = n.clone(),
+                (Some(i), None) => { children.remove(i); }
+                (None, Some(n)) => children.push(n.clone()),
+                (None, None) => {}
+            }
+        } else if let Some(child) = children
+            .iter_mut()
+            .find(|c| c.is_dir && target.starts_with(&c.path))
+        {
+            adjust_chain(child, target, update, delta, removed, added);
+        }
+        children.sort_by_key(|c| std::cmp::Reverse(c.size));
+    }
+
+    if is_parent {
+        // The parent's own entries changed, so keep its mtime current for
+        // incremental rescans.
+        if let Ok(meta) = std::fs::metadata(&node.path) {
+            node.last_modified = modified_secs(&meta);
+        }
+    }
+    node.size = (node.size as i64 + delta.0).max(0) as u64;
+    node.file_count = (node.file_count as i64 + delta.1).max(0) as u64;
+    node.apparent_size = (node.apparent_size + added.apparent).saturating_sub(removed.apparent);
+    node.allocated_size = (node.allocated_size + added.allocated).saturating_sub(removed.allocated);
+    if let Some(ft) = &mut node.file_types {
+        if let Some(r) = &removed.file_types {
+            ft.subtract(r);
+        }
+        if let Some(a) = &added.file_types {
+            ft.merge(a);
+        }
+    }
+    if let Some(owners) = &mut node.owners {
+        if let Some(r) = &removed.owners {
+            owners.subtract(r);
+        }
+        if let Some(a) = &added.owners {
+            owners.merge(a);
+        }
+    }
+}
+
+/// Seconds since the Unix epoch of a file's mtime, or 0 if unavailable.
+pub fn modified_secs(meta: &std::fs::Metadata) -> u64 {
+    meta.modified().ok()
+        .and_then(|t| t.duration_since(SystemTime::UNIX_EPOCH).ok())
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+#[cfg(unix)]
+pub fn inode_of(meta: &std::fs::Metadata) -> Option<u64> {
+    use std::os::unix::fs::MetadataExt;
+    Some(meta.ino())
+}
+
+#[cfg(not(unix))]
+pub fn inode_of(_meta: &std::fs::Metadata) -> Option<u64> {
+    None
+}
+
+// (uid, gid, permission bits) on Unix.
+#[cfg(unix)]
+pub fn ownership_of(meta: &std::fs::Metadata) -> (Option<u32>, Option<u32>, Option<u32>) {
+    use std::os::unix::fs::MetadataExt;
+    (Some(meta.uid()), Some(meta.gid()), Some(meta.mode() & 0o7777))
+}
+
+#[cfg(not(unix))]
+pub fn ownership_of(_meta: &std::fs::Metadata) -> (Option<u32>, Option<u32>, Option<u32>) {
+    (None, None, None)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TempDir;
+
+    #[cfg(unix)]
+    #[test]
+    fn hard_links_count_once_and_sparse_files_report_allocation() {
+        let root = TempDir::new("scan-links");
+        std::fs::create_dir_all(root.join("a")).unwrap();
+        std::fs::write(root.join("a/data"), vec![1u8; 8192]).unwrap();
+        std::fs::hard_link(root.join("a/data"), root.join("link")).unwrap();
+        std::fs::File::create(root.join("sparse.img")).unwrap().set_len(10 << 20).unwrap();
+
+        let node = scan_directory(root.to_str().unwrap(), &ScanOptions::default(), None, None).unwrap();
+        assert_eq!(node.apparent_size, 8192 + (10 << 20));
+        assert_eq!(node.size, node.apparent_size);
+        assert!(node.allocated_size < 1 << 20);
+
+        let options = ScanOptions { size_mode: SizeMode::Allocated, ..Default::default() };
+        let node = scan_directory(root.to_str().unwrap(), &options, None, None).unwrap();
+        assert_eq!(node.size, node.allocated_size);
+        assert_eq!(node.size_mode, Some(SizeMode::Allocated));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn symlink_policies_and_cycles() {
+        let root = TempDir::new("scan-symlinks");
+        std::fs::create_dir_all(root.join("a/b")).unwrap();
+        std::fs::write(root.join("a/b/data"), vec![1u8; 1000]).unwrap();
+        // A loop back to an ancestor, deep below the lookahead, plus a link
+        // at the top level into a real directory.
+        std::os::unix::fs::symlink(&root, root.join("a/b/loop")).unwrap();
+        std::os::unix::fs::symlink(root.join("a"), root.join("to_a")).unwrap();
+
+        let scan = |symlinks| {
+            let options = ScanOptions { symlinks, ..Default::default() };
+            scan_directory(root.to_str().unwrap(), &options, None, None).unwrap()
+        };
+        let child = |node: &FileNode, name: &str| {
+            node.children.as_ref().unwrap().iter().find(|c| c.name == name).cloned()
+        };
+
+        let ignored = scan(SymlinkPolicy::Ignore);
+        assert_eq!(ignored.apparent_size, 1000);
+        assert!(child(&ignored, "to_a").is_none());
+
+        let reported = scan(SymlinkPolicy::Report);
+        let link = child(&reported, "to_a").unwrap();
+        assert_eq!(link.kind, NodeKind::Symlink);
+        assert!(!link.is_dir);
+        assert_eq!(link.link_target.as_deref(), root.join("a").to_str());
+
+        // a/ is counted once, through the real directory; "to_a" is reported
+        // (sized by its target path, like a Report link) and the loop back to
+        // the root is left out.
+        let followed = scan(SymlinkPolicy::Follow);
+        assert_eq!(followed.apparent_size, 1000 + root.join("a").as_os_str().len() as u64);
+        assert_eq!(child(&followed, "to_a").unwrap().kind, NodeKind::Symlink);
+    }
+
+    #[test]
+    fn error_log_is_bounded_and_absent_when_clean() {
+        let root = TempDir::new("scan-errors");
+        std::fs::create_dir_all(root.join("a")).unwrap();
+        std::fs::write(root.join("a/data"), b"x").unwrap();
+
+        let node = scan_directory(root.to_str().unwrap(), &ScanOptions::default(), None, None).unwrap();
+        assert!(node.scan_errors.is_none());
+
+        let stats = Arc::new(ScanStats::new());
+        let ctx = ScanContext::new(&root, &ScanOptions::default(), Some(stats.clone()), None).unwrap();
+        assert!(ctx.list(&root.join("gone")).is_err());
+        for i in 0..MAX_REPORTED_ERRORS {
+            ctx.record_error(&root.join(i.to_string()), ScanErrorKind::Other, String::new());
+        }
+        let log = ctx.take_errors().unwrap();
+        assert_eq!(log.total, MAX_REPORTED_ERRORS as u64 + 1);
+        assert_eq!(log.errors.len(), MAX_REPORTED_ERRORS);
+        assert_eq!(log.errors[0].kind, ScanErrorKind::NotFound);
+        assert_eq!(stats.errors.load(Ordering::Relaxed), log.total);
+    }
+
+    #[test]
+    fn incremental_rescan_notices_changes_deep_in_reused_subtrees() {
+        let root = TempDir::new("scan-incremental");
+        std::fs::create_dir_all(root.join("a/b/c")).unwrap();
+        std::fs::write(root.join("a/b/c/f"), vec![0u8; 100]).unwrap();
+        let options = ScanOptions::default();
+        let rescan = |previous: &FileNode| {
+            scan_directory_incremental(previous, &options, Arc::new(DirSizes::default()), None, None).unwrap()
+        };
+
+        let first = scan_directory(root.to_str().unwrap(), &options, None, None).unwrap();
+        let same = rescan(&first);
+        assert_eq!(same.reused_subtrees, 1);
+        assert!(same.changed.is_empty());
+
+        // Only a/b/c's mtime changes; a/b, the aggregate, looks untouched.
+        std::fs::write(root.join("a/b/c/g"), vec![0u8; 10]).unwrap();
+        let grown = rescan(&same.node);
+        assert_eq!(grown.reused_subtrees, 0);
+        assert_eq!(grown.node.size, 110);
+        assert!(grown.changed.iter().any(|c| c.path == root.join("a/b").to_string_lossy()));
+    }
+
+    #[test]
+    fn update_points_stop_at_deep_aggregates() {
+        let node = |path: &str, children: Option<Vec<FileNode>>| FileNode {
+            path: path.to_string(),
+            is_dir: children.is_some() || path.ends_with("agg"),
+            children,
+            ..Default::default()
+        };
+        let tree = node("/r", Some(vec![node("/r/a", Some(vec![node("/r/a/agg", None)])), node("/r/f", None)]));
+        let point = |target: &str| update_point(&tree, std::path::Path::new(target));
+
+        assert_eq!(point("/r/a/new.txt"), Some("/r/a/new.txt".into()));
+        assert_eq!(point("/r/a/agg/x/y"), Some("/r/a/agg".into()));
+        assert_eq!(point("/r/new/x"), Some("/r/new".into()));
+        assert_eq!(point("/elsewhere"), None);
+    }
+
+    #[test]
+    fn depth_and_expansion_from_recorded_sizes() {
+        let root = TempDir::new("scan-depth");
+        std::fs::create_dir_all(root.join("a/b/c/d")).unwrap();
+        std::fs::write(root.join("a/b/c/d/f"), vec![0u8; 100]).unwrap();
+        std::fs::write(root.join("a/b/c/g"), vec![0u8; 10]).unwrap();
+        let path = root.to_str().unwrap();
+        let child = |node: &FileNode, name: &str| {
+            node.children.as_ref().unwrap().iter().find(|c| c.name == name).cloned().unwrap()
+        };
+
+        let options = ScanOptions { depth: 2, ..Default::default() };
+        let deep = scan_directory(path, &options, None, None).unwrap();
+        let b = child(&child(&deep, "a"), "b");
+        assert!(b.children.is_some());
+        assert!(child(&b, "c").children.is_none());
+
+        let sizes = Arc::new(DirSizes::default());
+        let shallow = scan_directory_with_sizes(path, &ScanOptions::default(), sizes.clone(), None, None).unwrap();
+        assert_eq!(shallow.size, 110);
+        // a/b is walked; a/b/c and a/b/c/d are recorded along the way.
+        assert_eq!(sizes.len(), 2);
+
+        // Recorded totals are used as-is, so a planted value shows through.
+        if let Ok(mut recorded) = sizes.0.lock() {
+            recorded.get_mut(&root.join("a/b/c")).unwrap().totals.size = 999;
+        }
+        let b = expand_dir(&root.join("a/b"), &ScanOptions::default(), sizes.clone()).unwrap();
+        assert_eq!(b.size, 999);
+        assert_eq!(child(&b, "c").file_count, 2);
+
+        // A change two levels down invalidates a/b/c's entry.
+        std::fs::write(root.join("a/b/c/d/h"), vec![0u8; 5]).unwrap();
+        let b = expand_dir(&root.join("a/b"), &ScanOptions::default(), sizes.clone()).unwrap();
+        assert_eq!(b.size, 115);
+    }
 }
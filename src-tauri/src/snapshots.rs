@@ -0,0 +1,146 @@
+// Named scan snapshots and snapshot diffs.
+//
+// "What filled up this disk since Monday": a cached scan tree can be saved
+// under a name (stored in the scan index DB, see scan_index.rs) and any two
+// snapshots of the same root compared node by node. The diff follows the
+// FileNode tree shape, so it is as deep as the scans were: below the
+// lookahead, directories only have aggregate sizes and the diff stops there.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use crate::scanner::FileNode;
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SnapshotInfo {
+    pub id: i64,
+    pub name: String,
+    pub root: String,
+    /// Seconds since the epoch.
+    pub created_at: u64,
+    pub size: u64,
+    pub file_count: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum DiffKind {
+    Added,
+    Removed,
+    Grown,
+    Shrunk,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct DiffEntry {
+    pub path: String,
+    pub kind: DiffKind,
+    pub is_dir: bool,
+    pub old_size: u64,
+    pub new_size: u64,
+    pub delta: i64,
+    pub old_file_count: u64,
+    pub new_file_count: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SnapshotDiff {
+    pub old: SnapshotInfo,
+    pub new: SnapshotInfo,
+    pub total_delta: i64,
+    /// Sorted by absolute byte delta, largest first. Nodes whose size did not
+    /// change are left out; added/removed directories are not expanded.
+    pub entries: Vec<DiffEntry>,
+}
+
+/// Diff two trees of the same root.
+pub fn diff_trees(old: &FileNode, new: &FileNode) -> Vec<DiffEntry> {
+    let mut entries = Vec::new();
+    diff_children(old, new, &mut entries);
+    entries.sort_by_key(|e| std::cmp::Reverse(e.delta.unsigned_abs()));
+    entries
+}
+
+fn diff_children(old: &FileNode, new: &FileNode, out: &mut Vec<DiffEntry>) {
+    let (old_children, new_children) = match (&old.children, &new.children) {
+        (Some(o), Some(n)) => (o, n),
+        // One side is a deep aggregate; its own delta was already recorded.
+        _ => return,
+    };
+
+    let old_by_path: HashMap<&str, &FileNode> =
+        old_children.iter().map(|c| (c.path.as_str(), c)).collect();
+
+    for n in new_children {
+        match old_by_path.get(n.path.as_str()) {
+            Some(o) => {
+                if o.size != n.size {
+                    let kind = if n.size > o.size { DiffKind::Grown } else { DiffKind::Shrunk };
+                    out.push(entry(n, kind, Some(o), Some(n)));
+                }
+                if o.is_dir && n.is_dir {
+                    diff_children(o, n, out);
+                }
+            }
+            None => out.push(entry(n, DiffKind::Added, None, Some(n))),
+        }
+    }
+
+    let new_paths: std::collections::HashSet<&str> =
+        new_children.iter().map(|c| c.path.as_str()).collect();
+    for o in old_children.iter().filter(|o| !new_paths.contains(o.path.as_str())) {
+        out.push(entry(o, DiffKind::Removed, Some(o), None));
+    }
+}
+
+fn entry(node: &FileNode, kind: DiffKind, old: Option<&FileNode>, new: Option<&FileNode>) -> DiffEntry {
+    let old_size = old.map(|n| n.size).unwrap_or(0);
+    let new_size = new.map(|n| n.size).unwrap_or(0);
+    DiffEntry {
+        path: node.path.clone(),
+        kind,
+        is_dir: node.is_dir,
+        old_size,
+        new_size,
+        delta: new_size as i64 - old_size as i64,
+        old_file_count: old.map(|n| n.file_count).unwrap_or(0),
+        new_file_count: new.map(|n| n.file_count).unwrap_or(0),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn node(path: &str, size: u64, children: Option<Vec<FileNode>>) -> FileNode {
+        FileNode {
+            name: path.rsplit('/').next().unwrap_or_default().to_string(),
+            path: path.to_string(),
+            size,
+            is_dir: children.is_some(),
+            file_count: 1,
+            children,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn reports_added_removed_grown_sorted_by_delta() {
+        let old = node("/r", 300, Some(vec![
+            node("/r/logs", 200, Some(vec![node("/r/logs/a.log", 200, None)])),
+            node("/r/gone.txt", 100, None),
+        ]));
+        let new = node("/r", 5200, Some(vec![
+            node("/r/logs", 1200, Some(vec![node("/r/logs/a.log", 1200, None)])),
+            node("/r/new.iso", 4000, None),
+        ]));
+
+        let diff = diff_trees(&old, &new);
+        let summary: Vec<(&str, DiffKind, i64)> =
+            diff.iter().map(|e| (e.path.as_str(), e.kind, e.delta)).collect();
+        assert_eq!(summary[0], ("/r/new.iso", DiffKind::Added, 4000));
+        assert!(summary.contains(&("/r/logs", DiffKind::Grown, 1000)));
+        assert!(summary.contains(&("/r/logs/a.log", DiffKind::Grown, 1000)));
+        assert_eq!(summary[3], ("/r/gone.txt", DiffKind::Removed, -100));
+    }
+}
@@ -0,0 +1,609 @@
+// Cleaner categories that are pruned by the owning tool, not by deleting
+// files.
+//
+// Package caches, the systemd journal, container images and Flatpak/Snap
+// leftovers are the biggest space users on our Linux servers, but removing
+// their files behind the tool's back corrupts its bookkeeping (a deleted
+// journal file journald still has open, a Docker layer some image still
+// references). Each category here reports what it could reclaim and runs
+// the tool's own clean/prune/vacuum command. A dry run shows the commands
+// that would run together with the tool's own simulation output where it has
+// a read-only one (`apt-get -s clean`), or our listing of what would go where
+// it doesn't. Dry runs never run anything that could change the system.
+//
+// Commands that need root go through pkexec when the app isn't root, so the
+// desktop asks for the password, once per prune: several commands run
+// together in one root shell. Dry runs never elevate.
+
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::time::SystemTime;
+
+use crate::cleaner::CleaningOptions;
+
+const DEFAULT_JOURNAL_DAYS: u32 = 30;
+const APT_ARCHIVES: &str = "/var/cache/apt/archives";
+const DNF_CACHES: [&str; 2] = ["/var/cache/dnf", "/var/cache/libdnf5"];
+const JOURNAL_DIRS: [&str; 2] = ["/var/log/journal", "/run/log/journal"];
+const SNAP_DIR: &str = "/var/lib/snapd/snaps";
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum CleanupTool {
+    Apt,
+    Dnf,
+    Journald,
+    Docker,
+    Podman,
+    Flatpak,
+    Snap,
+}
+
+const ALL_TOOLS: [CleanupTool; 7] = [
+    CleanupTool::Apt,
+    CleanupTool::Dnf,
+    CleanupTool::Journald,
+    CleanupTool::Docker,
+    CleanupTool::Podman,
+    CleanupTool::Flatpak,
+    CleanupTool::Snap,
+];
+
+/// A tool-pruned cleaner category. Only tools installed on this machine
+/// are reported.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ToolCategory {
+    pub tool: CleanupTool,
+    pub name: String,
+    pub description: String,
+    /// Bytes the prune would free; None when the tool can't tell
+    /// beforehand (Flatpak).
+    pub reclaimable: Option<u64>,
+    /// What would go, one line each (packages, images, revisions).
+    pub details: Vec<String>,
+    /// Commands a prune runs, in order.
+    pub commands: Vec<Vec<String>>,
+    pub needs_root: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct PruneResult {
+    pub tool: CleanupTool,
+    pub dry_run: bool,
+    /// As actually run, including any pkexec prefix.
+    pub commands: Vec<Vec<String>>,
+    pub success: bool,
+    /// Combined stdout/stderr of the commands, or the dry-run report.
+    pub output: String,
+    pub reclaimable_before: Option<u64>,
+    pub reclaimable_after: Option<u64>,
+}
+
+/// Categories for every supported tool found on this machine.
+pub fn scan_tool_categories(options: &CleaningOptions) -> Vec<ToolCategory> {
+    if !cfg!(target_os = "linux") {
+        return Vec::new();
+    }
+    ALL_TOOLS.iter().filter_map(|tool| inspect(*tool, options)).collect()
+}
+
+/// Run (or with `options.dry_run`, describe) the prune for `tool`.
+pub fn prune(tool: CleanupTool, options: &CleaningOptions) -> Result<PruneResult, String> {
+    if !cfg!(target_os = "linux") {
+        return Err("Tool cleanup is only supported on Linux".to_string());
+    }
+    let category = inspect(tool, options).ok_or_else(|| format!("{:?} is not available on this system", tool))?;
+    if category.commands.is_empty() {
+        return Ok(PruneResult {
+            tool,
+            dry_run: options.dry_run,
+            commands: Vec::new(),
+            success: true,
+            output: "Nothing to clean".to_string(),
+            reclaimable_before: category.reclaimable,
+            reclaimable_after: category.reclaimable,
+        });
+    }
+
+    if options.dry_run {
+        return Ok(PruneResult {
+            tool,
+            dry_run: true,
+            output: dry_run_report(&category),
+            success: true,
+            commands: category.commands,
+            reclaimable_before: category.reclaimable,
+            reclaimable_after: category.reclaimable,
+        });
+    }
+
+    let commands: Vec<Vec<String>> = if category.needs_root && !is_root() {
+        if which("pkexec").is_none() {
+            return Err(format!("Cleaning {} needs root and pkexec is not installed", category.name));
+        }
+        vec![elevated(&category.commands)]
+    } else {
+        category.commands.clone()
+    };
+    let mut output = String::new();
+    let mut success = true;
+    for command in &commands {
+        output.push_str(&format!("$ {}\n", command.join(" ")));
+        match run(command) {
+            Ok((ok, text)) => {
+                output.push_str(&text);
+                success &= ok;
+            }
+            Err(e) => {
+                output.push_str(&e);
+                success = false;
+            }
+        }
+        if !success {
+            break;
+        }
+    }
+
+    Ok(PruneResult {
+        tool,
+        dry_run: false,
+        commands,
+        success,
+        output,
+        reclaimable_before: category.reclaimable,
+        reclaimable_after: inspect(tool, options).and_then(|c| c.reclaimable),
+    })
+}
+
+fn inspect(tool: CleanupTool, options: &CleaningOptions) -> Option<ToolCategory> {
+    match tool {
+        CleanupTool::Apt => {
+            which("apt-get")?;
+            let files = files_with_extension(&[Path::new(APT_ARCHIVES), &Path::new(APT_ARCHIVES).join("partial")], "deb");
+            Some(file_category(
+                tool,
+                "APT package cache",
+                "Downloaded .deb packages kept after installing",
+                files,
+                vec![args(&["apt-get", "clean"])],
+            ))
+        }
+        CleanupTool::Dnf => {
+            which("dnf")?;
+            let roots: Vec<PathBuf> = DNF_CACHES.iter().map(PathBuf::from).collect();
+            let files = files_with_extension_recursive(&roots, "rpm");
+            Some(file_category(
+                tool,
+                "DNF package cache",
+                "Downloaded .rpm packages kept after installing",
+                files,
+                vec![args(&["dnf", "clean", "packages"])],
+            ))
+        }
+        CleanupTool::Journald => {
+            which("journalctl")?;
+            let mut max_age = options.journal_max_age_days.or(options.min_age_days);
+            if options.journal_max_size.is_none() {
+                max_age = max_age.or(Some(DEFAULT_JOURNAL_DAYS));
+            }
+            let files = journal_vacuum_candidates(journal_files(), SystemTime::now(), max_age, options.journal_max_size);
+
+            let mut command = args(&["journalctl"]);
+            let mut limits = Vec::new();
+            if let Some(days) = max_age {
+                command.push(format!("--vacuum-time={}d", days));
+                limits.push(format!("older than {} days", days));
+            }
+            if let Some(bytes) = options.journal_max_size {
+                command.push(format!("--vacuum-size={}", bytes));
+                limits.push(format!("beyond {} in total", format_size(bytes)));
+            }
+            Some(file_category(
+                tool,
+                "Journal logs",
+                &format!("Archived systemd journal files {}", limits.join(" or ")),
+                files,
+                vec![command],
+            ))
+        }
+        CleanupTool::Docker => container_category(tool, "docker", "Docker"),
+        CleanupTool::Podman => container_category(tool, "podman", "Podman"),
+        CleanupTool::Flatpak => {
+            which("flatpak")?;
+            Some(ToolCategory {
+                tool,
+                name: "Flatpak unused runtimes".to_string(),
+                description: "Runtimes and extensions no installed app uses any more".to_string(),
+                reclaimable: None,
+                details: Vec::new(),
+                commands: vec![args(&["flatpak", "uninstall", "--unused", "--noninteractive", "-y"])],
+                needs_root: false,
+            })
+        }
+        CleanupTool::Snap => {
+            which("snap")?;
+            let (ok, listing) = run(&args(&["snap", "list", "--all"])).ok()?;
+            if !ok {
+                return None;
+            }
+            let revisions = disabled_snap_revisions(&listing);
+            let mut reclaimable = 0;
+            let mut details = Vec::new();
+            for (name, revision) in &revisions {
+                let size = std::fs::metadata(Path::new(SNAP_DIR).join(format!("{}_{}.snap", name, revision)))
+                    .map(|m| m.len())
+                    .unwrap_or(0);
+                reclaimable += size;
+                details.push(format!("{} revision {} ({})", name, revision, format_size(size)));
+            }
+            Some(ToolCategory {
+                tool,
+                name: "Snap old revisions".to_string(),
+                description: "Disabled snap revisions kept for rollback".to_string(),
+                reclaimable: Some(reclaimable),
+                details,
+                commands: revisions
+                    .iter()
+                    .map(|(name, revision)| {
+                        vec!["snap".to_string(), "remove".to_string(), name.clone(), format!("--revision={}", revision)]
+                    })
+                    .collect(),
+                needs_root: true,
+            })
+        }
+    }
+}
+
+// Category for root-owned cache files the tool removes itself.
+fn file_category(
+    tool: CleanupTool,
+    name: &str,
+    description: &str,
+    files: Vec<(PathBuf, u64)>,
+    commands: Vec<Vec<String>>,
+) -> ToolCategory {
+    ToolCategory {
+        tool,
+        name: name.to_string(),
+        description: description.to_string(),
+        reclaimable: Some(files.iter().map(|(_, size)| size).sum()),
+        details: files
+            .iter()
+            .map(|(path, size)| format!("{} ({})", path.display(), format_size(*size)))
+            .collect(),
+        commands: if files.is_empty() { Vec::new() } else { commands },
+        needs_root: true,
+    }
+}
+
+// Dangling images, plus Docker's build cache. Podman builds leave dangling
+// images rather than a separate cache.
+fn container_category(tool: CleanupTool, binary: &str, label: &str) -> Option<ToolCategory> {
+    which(binary)?;
+    let (ok, images) = run(&args(&[binary, "images", "--filter", "dangling=true", "--format", "{{.ID}}\t{{.Size}}"])).ok()?;
+    if !ok {
+        // Daemon not running, or no permission to talk to it.
+        return None;
+    }
+    let mut reclaimable = 0;
+    let mut details = Vec::new();
+    for line in images.lines().filter(|l| !l.trim().is_empty()) {
+        let (id, size) = line.split_once('\t').unwrap_or((line, ""));
+        let size = parse_human_size(size).unwrap_or(0);
+        reclaimable += size;
+        details.push(format!("dangling image {} ({})", id, format_size(size)));
+    }
+    let mut commands = vec![args(&[binary, "image", "prune", "-f"])];
+
+    if tool == CleanupTool::Docker {
+        let df = run(&args(&["docker", "system", "df", "--format", "{{.Type}}\t{{.Reclaimable}}"]));
+        if let Ok((true, df)) = df {
+            if let Some(size) = build_cache_reclaimable(&df) {
+                reclaimable += size;
+                details.push(format!("build cache ({})", format_size(size)));
+            }
+        }
+        commands.push(args(&["docker", "builder", "prune", "-f"]));
+    }
+
+    Some(ToolCategory {
+        tool,
+        name: format!("{} images and build cache", label),
+        description: format!("Dangling {} images and unused build layers", label),
+        reclaimable: Some(reclaimable),
+        details,
+        commands: if reclaimable > 0 { commands } else { Vec::new() },
+        needs_root: false,
+    })
+}
+
+fn dry_run_report(category: &ToolCategory) -> String {
+    let mut report = String::from("Would run:\n");
+    for command in &category.commands {
+        report.push_str(&format!("  {}{}\n", if category.needs_root { "(as root) " } else { "" }, command.join(" ")));
+    }
+    // The tool's own simulation, where it has a read-only one. Flatpak's
+    // only listing of unused refs is the uninstall itself.
+    let simulated = match category.tool {
+        CleanupTool::Apt => run(&args(&["apt-get", "-s", "clean"])).ok(),
+        _ => None,
+    };
+    report.push('\n');
+    match simulated {
+        Some((_, output)) => report.push_str(&output),
+        None => {
+            if let Some(size) = category.reclaimable {
+                report.push_str(&format!("Reclaimable: {}\n", format_size(size)));
+            }
+            for line in &category.details {
+                report.push_str(line);
+                report.push('\n');
+            }
+        }
+    }
+    report
+}
+
+// `commands` as a single pkexec invocation, so a batch (one `snap remove`
+// per revision) asks for the password once. Stops at the first failure.
+fn elevated(commands: &[Vec<String>]) -> Vec<String> {
+    if let [command] = commands {
+        return [vec!["pkexec".to_string()], command.clone()].concat();
+    }
+    let script = commands
+        .iter()
+        .map(|command| command.iter().map(|arg| shell_quote(arg)).collect::<Vec<_>>().join(" "))
+        .collect::<Vec<_>>()
+        .join(" && ");
+    args(&["pkexec", "sh", "-c", &script])
+}
+
+fn shell_quote(arg: &str) -> String {
+    format!("'{}'", arg.replace('\'', "'\\''"))
+}
+
+fn args(parts: &[&str]) -> Vec<String> {
+    parts.iter().map(|p| p.to_string()).collect()
+}
+
+// Runs a command without a shell; returns whether it succeeded and its
+// stdout followed by stderr.
+fn run(command: &[String]) -> Result<(bool, String), String> {
+    let (program, rest) = command.split_first().ok_or_else(|| "Empty command".to_string())?;
+    let output = Command::new(program)
+        .args(rest)
+        .env("LC_ALL", "C")
+        .stdin(Stdio::null())
+        .output()
+        .map_err(|e| format!("Failed to run {}: {}", program, e))?;
+    let mut text = String::from_utf8_lossy(&output.stdout).to_string();
+    text.push_str(&String::from_utf8_lossy(&output.stderr));
+    Ok((output.status.success(), text))
+}
+
+fn which(binary: &str) -> Option<PathBuf> {
+    let path = std::env::var_os("PATH")?;
+    std::env::split_paths(&path).map(|dir| dir.join(binary)).find(|p| p.is_file())
+}
+
+#[cfg(target_os = "linux")]
+fn is_root() -> bool {
+    // SAFETY: geteuid has no preconditions and cannot fail.
+    unsafe { libc::geteuid() == 0 }
+}
+
+#[cfg(not(target_os = "linux"))]
+fn is_root() -> bool {
+    false
+}
+
+fn files_with_extension(dirs: &[&Path], extension: &str) -> Vec<(PathBuf, u64)> {
+    let mut files = Vec::new();
+    for dir in dirs {
+        for entry in std::fs::read_dir(dir).into_iter().flatten().flatten() {
+            let path = entry.path();
+            if path.extension().is_some_and(|e| e == extension) {
+                if let Ok(meta) = entry.metadata() {
+                    files.push((path, meta.len()));
+                }
+            }
+        }
+    }
+    files
+}
+
+fn files_with_extension_recursive(roots: &[PathBuf], extension: &str) -> Vec<(PathBuf, u64)> {
+    roots
+        .iter()
+        .flat_map(|root| walkdir::WalkDir::new(root).into_iter().flatten())
+        .filter(|e| e.file_type().is_file() && e.path().extension().is_some_and(|x| x == extension))
+        .filter_map(|e| e.metadata().ok().map(|m| (e.into_path(), m.len())))
+        .collect()
+}
+
+struct JournalFile {
+    path: PathBuf,
+    size: u64,
+    modified: SystemTime,
+    archived: bool,
+}
+
+fn journal_files() -> Vec<JournalFile> {
+    JOURNAL_DIRS
+        .iter()
+        .flat_map(|root| walkdir::WalkDir::new(root).max_depth(2).into_iter().flatten())
+        .filter(|e| e.file_type().is_file())
+        .filter_map(|e| {
+            let name = e.file_name().to_string_lossy().to_string();
+            if !name.ends_with(".journal") && !name.ends_with(".journal~") {
+                return None;
+            }
+            let meta = e.metadata().ok()?;
+            Some(JournalFile {
+                path: e.into_path(),
+                size: meta.len(),
+                modified: meta.modified().ok()?,
+                archived: is_archived_journal(&name),
+            })
+        })
+        .collect()
+}
+
+// What `journalctl --vacuum-time --vacuum-size` would remove: archived
+// files last written more than `max_age_days` ago, then the oldest remaining
+// archived files until the whole journal fits in `max_size`. Active files
+// (no '@' in the name) are never vacuumed but count towards the size.
+fn journal_vacuum_candidates(
+    mut files: Vec<JournalFile>,
+    now: SystemTime,
+    max_age_days: Option<u32>,
+    max_size: Option<u64>,
+) -> Vec<(PathBuf, u64)> {
+    files.sort_by_key(|f| f.modified);
+    let mut total: u64 = files.iter().map(|f| f.size).sum();
+    let cutoff = max_age_days.map(|days| now - std::time::Duration::from_secs(u64::from(days) * 86_400));
+    let mut removed = Vec::new();
+    for file in files.into_iter().filter(|f| f.archived) {
+        let expired = cutoff.is_some_and(|cutoff| file.modified < cutoff);
+        let over_size = max_size.is_some_and(|max| total > max);
+        if expired || over_size {
+            total -= file.size;
+            removed.push((file.path, file.size));
+        }
+    }
+    removed
+}
+
+// Files journald has rotated away from (`system@...journal`,
+// `user-1000@...journal~`); the ones it is writing have no '@'.
+fn is_archived_journal(name: &str) -> bool {
+    name.contains('@') && (name.ends_with(".journal") || name.ends_with(".journal~"))
+}
+
+// `snap list --all`: Name Version Rev Tracking Publisher Notes, where Notes
+// lists "disabled" for revisions kept for rollback.
+fn disabled_snap_revisions(listing: &str) -> Vec<(String, String)> {
+    listing
+        .lines()
+        .skip(1)
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            let disabled = fields.get(5).is_some_and(|notes| notes.split(',').any(|n| n == "disabled"));
+            disabled.then(|| (fields[0].to_string(), fields[2].to_string()))
+        })
+        .collect()
+}
+
+// The "Build Cache" row of `docker system df`, e.g. "1.2GB" or "1.2GB (50%)".
+fn build_cache_reclaimable(df: &str) -> Option<u64> {
+    df.lines()
+        .find_map(|line| line.strip_prefix("Build Cache\t"))
+        .and_then(|value| parse_human_size(value.split(" (").next().unwrap_or(value)))
+}
+
+// Docker and Podman print decimal sizes: "0B", "12.3kB", "456MB", "1.2 GB".
+fn parse_human_size(text: &str) -> Option<u64> {
+    let text: String = text.chars().filter(|c| !c.is_whitespace()).collect();
+    let split = text.find(|c: char| !(c.is_ascii_digit() || c == '.'))?;
+    let (number, unit) = text.split_at(split);
+    let number: f64 = number.parse().ok()?;
+    let multiplier = match unit.to_ascii_uppercase().as_str() {
+        "B" => 1e0,
+        "KB" => 1e3,
+        "MB" => 1e6,
+        "GB" => 1e9,
+        "TB" => 1e12,
+        _ => return None,
+    };
+    Some((number * multiplier) as u64)
+}
+
+fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["B", "KB", "MB", "GB", "TB"];
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} B", bytes)
+    } else {
+        format!("{:.1} {}", size, UNITS[unit])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_tool_output() {
+        assert_eq!(parse_human_size("456MB"), Some(456_000_000));
+        assert_eq!(parse_human_size("1.5 GB"), Some(1_500_000_000));
+        assert_eq!(parse_human_size("0B"), Some(0));
+        assert_eq!(parse_human_size("n/a"), None);
+        assert_eq!(build_cache_reclaimable("Images\t2GB (40%)\nBuild Cache\t1.2GB\n"), Some(1_200_000_000));
+
+        let listing = "Name    Version  Rev    Tracking       Publisher   Notes\n\
+                       core20  20240111 2105   latest/stable  canonical✓  base,disabled\n\
+                       core20  20240416 2264   latest/stable  canonical✓  base\n\
+                       firefox 125.0    4173   latest/stable  mozilla✓    disabled\n";
+        assert_eq!(
+            disabled_snap_revisions(listing),
+            vec![("core20".to_string(), "2105".to_string()), ("firefox".to_string(), "4173".to_string())]
+        );
+
+        assert!(is_archived_journal("system@00061d1c1c8a7b3a-9d1b4d1d5a4c5e2f.journal~"));
+        assert!(is_archived_journal("user-1000@5c1b0e3d6f2a4b8e9c7d1a2b3c4d5e6f-0000000000001234-00061d1c1c8a7b3a.journal"));
+        assert!(!is_archived_journal("system.journal"));
+    }
+
+    #[test]
+    fn elevates_a_batch_once() {
+        assert_eq!(elevated(&[args(&["apt-get", "clean"])]), args(&["pkexec", "apt-get", "clean"]));
+
+        let batch = elevated(&[
+            args(&["snap", "remove", "core20", "--revision=2105"]),
+            args(&["snap", "remove", "it's", "--revision=1"]),
+        ]);
+        assert_eq!(batch[..3], args(&["pkexec", "sh", "-c"])[..]);
+        assert_eq!(
+            batch[3],
+            "'snap' 'remove' 'core20' '--revision=2105' && 'snap' 'remove' 'it'\\''s' '--revision=1'"
+        );
+        let (ok, echoed) = run(&args(&["sh", "-c", &format!("echo {}", shell_quote("it's $HOME"))])).unwrap();
+        assert!(ok);
+        assert_eq!(echoed, "it's $HOME\n");
+    }
+
+    #[test]
+    fn journal_vacuum_by_time_and_size() {
+        let now = SystemTime::now();
+        let file = |name: &str, size: u64, days: u64| JournalFile {
+            path: PathBuf::from(name),
+            size,
+            modified: now - std::time::Duration::from_secs(days * 86_400),
+            archived: is_archived_journal(name),
+        };
+        let files = || {
+            vec![
+                file("system.journal", 100, 0),
+                file("system@a.journal", 100, 40),
+                file("system@b.journal", 100, 20),
+                file("user-1000@c.journal", 100, 10),
+            ]
+        };
+        let names = |removed: Vec<(PathBuf, u64)>| removed.into_iter().map(|(p, _)| p).collect::<Vec<_>>();
+
+        assert_eq!(names(journal_vacuum_candidates(files(), now, Some(30), None)), vec![PathBuf::from("system@a.journal")]);
+        assert_eq!(
+            names(journal_vacuum_candidates(files(), now, None, Some(250))),
+            vec![PathBuf::from("system@a.journal"), PathBuf::from("system@b.journal")]
+        );
+        // The active file alone is over the limit; only archived ones go.
+        assert_eq!(journal_vacuum_candidates(files(), now, Some(365), Some(50)).len(), 3);
+        assert!(journal_vacuum_candidates(files(), now, None, None).is_empty());
+    }
+}
@@ -0,0 +1,431 @@
+// Move-to-trash and restore, following the freedesktop.org Trash spec.
+//
+// Items on the home volume go to $XDG_DATA_HOME/Trash (~/.local/share/Trash);
+// items on other volumes go to that volume's $topdir/.Trash/$uid when an
+// admin has set up a sticky .Trash, else to $topdir/.Trash-$uid, so nothing
+// is ever copied across devices. Each item gets a files/<name> entry and an
+// info/<name>.trashinfo recording where it came from, which is what file
+// managers read to offer "Restore". The optional directorysizes cache is not
+// maintained; file managers recompute it.
+//
+// Linux only for now: elsewhere `SUPPORTED` is false, `trash` returns an
+// error, and the file explorer offers only an explicit permanent delete.
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct TrashedItem {
+    /// The .trashinfo file; identifies the item for `restore`.
+    pub info_path: String,
+    pub original_path: String,
+    /// Where the item now lives inside the trash.
+    pub trashed_path: String,
+    /// Local time, as written in the .trashinfo (`YYYY-MM-DDThh:mm:ss`).
+    pub deleted_at: String,
+    pub is_dir: bool,
+}
+
+pub use platform::{list_trash, restore, trash, SUPPORTED};
+
+#[cfg(target_os = "linux")]
+mod platform {
+    use super::TrashedItem;
+    use crate::mounts;
+    use std::fs::{self, OpenOptions};
+    use std::io::Write;
+    use std::os::unix::fs::{DirBuilderExt, MetadataExt, PermissionsExt};
+    use std::path::{Path, PathBuf};
+
+    pub const SUPPORTED: bool = true;
+
+    const INFO_EXTENSION: &str = "trashinfo";
+
+    // A trash directory (containing files/ and info/) and, for per-volume
+    // trashes, the volume's top directory that info paths are relative to.
+    pub(super) struct TrashDir {
+        pub root: PathBuf,
+        pub topdir: Option<PathBuf>,
+    }
+
+    impl TrashDir {
+        fn files(&self) -> PathBuf {
+            self.root.join("files")
+        }
+
+        fn info(&self) -> PathBuf {
+            self.root.join("info")
+        }
+
+        fn ensure(&self) -> Result<(), String> {
+            for dir in [self.files(), self.info()] {
+                fs::DirBuilder::new()
+                    .recursive(true)
+                    .mode(0o700)
+                    .create(&dir)
+                    .map_err(|e| format!("Cannot create trash directory {}: {}", dir.display(), e))?;
+            }
+            Ok(())
+        }
+
+        // The spec's Path= value: relative to the volume top for per-volume
+        // trashes, absolute for the home trash.
+        fn info_path_for(&self, original: &Path) -> PathBuf {
+            match &self.topdir {
+                Some(top) => original.strip_prefix(top).unwrap_or(original).to_path_buf(),
+                None => original.to_path_buf(),
+            }
+        }
+
+        fn original_from_info(&self, recorded: &Path) -> PathBuf {
+            match &self.topdir {
+                Some(top) if recorded.is_relative() => top.join(recorded),
+                _ => recorded.to_path_buf(),
+            }
+        }
+    }
+
+    fn uid() -> u32 {
+        unsafe { libc::getuid() }
+    }
+
+    fn home_trash() -> Result<TrashDir, String> {
+        let data_home = std::env::var_os("XDG_DATA_HOME")
+            .map(PathBuf::from)
+            .filter(|p| p.is_absolute())
+            .or_else(|| dirs::home_dir().map(|h| h.join(".local/share")))
+            .ok_or_else(|| "Could not resolve home directory".to_string())?;
+        Ok(TrashDir { root: data_home.join("Trash"), topdir: None })
+    }
+
+    // Per-volume trash for `topdir`, per the spec: $topdir/.Trash/$uid if
+    // .Trash is a real sticky directory, else $topdir/.Trash-$uid.
+    fn volume_trash(topdir: &Path) -> TrashDir {
+        let shared = topdir.join(".Trash");
+        let usable_shared = fs::symlink_metadata(&shared)
+            .map(|m| m.is_dir() && m.permissions().mode() & 0o1000 != 0)
+            .unwrap_or(false);
+        let root = if usable_shared {
+            shared.join(uid().to_string())
+        } else {
+            topdir.join(format!(".Trash-{}", uid()))
+        };
+        TrashDir { root, topdir: Some(topdir.to_path_buf()) }
+    }
+
+    fn mount_point_of(path: &Path) -> Option<PathBuf> {
+        mounts::read_mounts()
+            .into_iter()
+            .map(|m| m.mount_point)
+            .filter(|mp| path.starts_with(mp))
+            .max_by_key(|mp| mp.as_os_str().len())
+    }
+
+    fn trash_for(path: &Path, dev: u64) -> Result<TrashDir, String> {
+        let home = home_trash()?;
+        home.ensure()?;
+        let home_dev = fs::metadata(&home.root).map_err(|e| e.to_string())?.dev();
+        if home_dev == dev {
+            return Ok(home);
+        }
+        let topdir = mount_point_of(path)
+            .ok_or_else(|| format!("Cannot find the volume of {}", path.display()))?;
+        let trash = volume_trash(&topdir);
+        trash.ensure()?;
+        Ok(trash)
+    }
+
+    /// Move `path` to the trash of its volume.
+    pub fn trash(path: &Path) -> Result<TrashedItem, String> {
+        let path = if path.is_absolute() {
+            path.to_path_buf()
+        } else {
+            std::env::current_dir().map_err(|e| e.to_string())?.join(path)
+        };
+        let meta = fs::symlink_metadata(&path).map_err(|_| "Path does not exist".to_string())?;
+        let trash = trash_for(&path, meta.dev())?;
+        if path.starts_with(&trash.root) {
+            return Err("Item is already in the trash".to_string());
+        }
+        move_to(&trash, &path, meta.is_dir())
+    }
+
+    pub(super) fn move_to(trash: &TrashDir, path: &Path, is_dir: bool) -> Result<TrashedItem, String> {
+        let base = path
+            .file_name()
+            .ok_or_else(|| "Cannot trash a filesystem root".to_string())?
+            .to_string_lossy()
+            .to_string();
+        let deleted_at = chrono::Local::now().format("%Y-%m-%dT%H:%M:%S").to_string();
+        let contents = format!(
+            "[Trash Info]\nPath={}\nDeletionDate={}\n",
+            encode_path(&trash.info_path_for(path)),
+            deleted_at,
+        );
+
+        // Creating the .trashinfo with O_EXCL reserves the name against
+        // concurrent trashers (other apps included) before the move.
+        for attempt in 1..10_000 {
+            let name = if attempt == 1 { base.clone() } else { format!("{}.{}", base, attempt) };
+            let info_path = trash.info().join(format!("{}.{}", name, INFO_EXTENSION));
+            let trashed_path = trash.files().join(&name);
+            if fs::symlink_metadata(&trashed_path).is_ok() {
+                continue;
+            }
+            let mut info = match OpenOptions::new().write(true).create_new(true).open(&info_path) {
+                Ok(f) => f,
+                Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => continue,
+                Err(e) => return Err(format!("Cannot write {}: {}", info_path.display(), e)),
+            };
+            let moved = info
+                .write_all(contents.as_bytes())
+                .and_then(|_| info.sync_all())
+                .and_then(|_| fs::rename(path, &trashed_path));
+            if let Err(e) = moved {
+                let _ = fs::remove_file(&info_path);
+                return Err(format!("Cannot move {} to the trash: {}", path.display(), e));
+            }
+            return Ok(TrashedItem {
+                info_path: info_path.to_string_lossy().to_string(),
+                original_path: path.to_string_lossy().to_string(),
+                trashed_path: trashed_path.to_string_lossy().to_string(),
+                deleted_at,
+                is_dir,
+            });
+        }
+        Err(format!("Too many items named {} in the trash", base))
+    }
+
+    // The home trash and this user's trashes on every mounted volume.
+    fn trash_dirs() -> Result<Vec<TrashDir>, String> {
+        let mut trashes = vec![home_trash()?];
+        for mount in mounts::read_mounts() {
+            if !mounts::is_virtual_fs(&mount.fs_type) {
+                trashes.push(volume_trash(&mount.mount_point));
+                trashes.push(TrashDir {
+                    root: mount.mount_point.join(format!(".Trash-{}", uid())),
+                    topdir: Some(mount.mount_point),
+                });
+            }
+        }
+        let mut seen = std::collections::HashSet::new();
+        trashes.retain(|t| seen.insert(t.root.clone()));
+        Ok(trashes)
+    }
+
+    /// Items in the home trash and in this user's trash on every mounted
+    /// volume, newest first.
+    pub fn list_trash() -> Result<Vec<TrashedItem>, String> {
+        let mut items = Vec::new();
+        for trash in trash_dirs()? {
+            let entries = match fs::read_dir(trash.info()) {
+                Ok(e) => e,
+                Err(_) => continue,
+            };
+            for entry in entries.filter_map(|e| e.ok()) {
+                if let Some(item) = read_item(&trash, &entry.path()) {
+                    items.push(item);
+                }
+            }
+        }
+        items.sort_by(|a, b| b.deleted_at.cmp(&a.deleted_at));
+        Ok(items)
+    }
+
+    // The trash an info file belongs to, <root>/info/<name>.trashinfo, if
+    // <root> is one of the trashes `list_trash` reads.
+    fn trash_of_info(trashes: Vec<TrashDir>, info_path: &Path) -> Option<TrashDir> {
+        let info_dir = info_path.parent()?;
+        trashes.into_iter().find(|t| t.info() == info_dir)
+    }
+
+    fn read_item(trash: &TrashDir, info_path: &Path) -> Option<TrashedItem> {
+        if info_path.extension()? != INFO_EXTENSION {
+            return None;
+        }
+        let contents = fs::read_to_string(info_path).ok()?;
+        let (recorded, deleted_at) = parse_trashinfo(&contents)?;
+        let original = trash.original_from_info(&recorded);
+        if !original.is_absolute() {
+            return None;
+        }
+        let trashed_path = trash.files().join(info_path.file_stem()?);
+        let meta = fs::symlink_metadata(&trashed_path).ok()?;
+        Some(TrashedItem {
+            info_path: info_path.to_string_lossy().to_string(),
+            original_path: original.to_string_lossy().to_string(),
+            trashed_path: trashed_path.to_string_lossy().to_string(),
+            deleted_at,
+            is_dir: meta.is_dir(),
+        })
+    }
+
+    /// Move a trashed item back to where it was deleted from. Fails rather
+    /// than overwrite if something has been created there since. Only info
+    /// files in the user's own trashes are accepted.
+    pub fn restore(info_path: &Path) -> Result<TrashedItem, String> {
+        let trash = trash_of_info(trash_dirs()?, info_path).ok_or_else(|| "Not a trash info file".to_string())?;
+        restore_from(&trash, info_path)
+    }
+
+    pub(super) fn restore_from(trash: &TrashDir, info_path: &Path) -> Result<TrashedItem, String> {
+        let item = read_item(trash, info_path).ok_or_else(|| "Trashed item not found".to_string())?;
+        let original = Path::new(&item.original_path);
+        if fs::symlink_metadata(original).is_ok() {
+            return Err(format!("{} already exists", original.display()));
+        }
+        if let Some(parent) = original.parent() {
+            fs::create_dir_all(parent).map_err(|e| format!("Cannot recreate {}: {}", parent.display(), e))?;
+        }
+        fs::rename(&item.trashed_path, original)
+            .map_err(|e| format!("Cannot restore {}: {}", original.display(), e))?;
+        let _ = fs::remove_file(info_path);
+        Ok(item)
+    }
+
+    fn parse_trashinfo(contents: &str) -> Option<(PathBuf, String)> {
+        let mut lines = contents.lines().map(str::trim);
+        if lines.next()? != "[Trash Info]" {
+            return None;
+        }
+        let (mut path, mut date) = (None, String::new());
+        for line in lines {
+            if let Some(value) = line.strip_prefix("Path=") {
+                let decoded = PathBuf::from(decode_path(value)?);
+                // A `..` could restore to anywhere.
+                if decoded.components().any(|c| c == std::path::Component::ParentDir) {
+                    return None;
+                }
+                path = Some(decoded);
+            } else if let Some(value) = line.strip_prefix("DeletionDate=") {
+                date = value.to_string();
+            }
+        }
+        Some((path?, date))
+    }
+
+    // Path= values are URL-escaped (RFC 2396), keeping `/`.
+    fn encode_path(path: &Path) -> String {
+        use std::os::unix::ffi::OsStrExt;
+        let mut out = String::new();
+        for &b in path.as_os_str().as_bytes() {
+            if b.is_ascii_alphanumeric() || b"-_.~/".contains(&b) {
+                out.push(b as char);
+            } else {
+                out.push_str(&format!("%{:02X}", b));
+            }
+        }
+        out
+    }
+
+    fn decode_path(value: &str) -> Option<std::ffi::OsString> {
+        use std::os::unix::ffi::OsStringExt;
+        let bytes = value.as_bytes();
+        let mut out = Vec::with_capacity(bytes.len());
+        let mut i = 0;
+        while i < bytes.len() {
+            if bytes[i] == b'%' {
+                let hex = std::str::from_utf8(bytes.get(i + 1..i + 3)?).ok()?;
+                out.push(u8::from_str_radix(hex, 16).ok()?);
+                i += 3;
+            } else {
+                out.push(bytes[i]);
+                i += 1;
+            }
+        }
+        Some(std::ffi::OsString::from_vec(out))
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use crate::test_support::TempDir;
+
+        #[test]
+        fn trash_and_restore_round_trip_with_name_collisions() {
+            let base = TempDir::new("trash");
+            let trash = TrashDir { root: base.join("Trash"), topdir: None };
+            trash.ensure().unwrap();
+            let dir = base.join("data dir");
+            fs::create_dir_all(&dir).unwrap();
+
+            fs::write(dir.join("report 100%.txt"), b"one").unwrap();
+            let first = move_to(&trash, &dir.join("report 100%.txt"), false).unwrap();
+            fs::write(dir.join("report 100%.txt"), b"two").unwrap();
+            let second = move_to(&trash, &dir.join("report 100%.txt"), false).unwrap();
+            assert!(second.trashed_path.ends_with("report 100%.txt.2"));
+
+            let info = fs::read_to_string(&first.info_path).unwrap();
+            assert!(info.contains("/data%20dir/report%20100%25.txt\n"));
+
+            // Restoring refuses to overwrite the newer file...
+            fs::write(dir.join("report 100%.txt"), b"three").unwrap();
+            assert!(restore_from(&trash, Path::new(&first.info_path)).is_err());
+            fs::remove_file(dir.join("report 100%.txt")).unwrap();
+
+            // ...and otherwise puts the item back and drops its info file.
+            let restored = restore_from(&trash, Path::new(&first.info_path)).unwrap();
+            assert_eq!(restored.original_path, dir.join("report 100%.txt").to_string_lossy());
+            assert_eq!(fs::read(dir.join("report 100%.txt")).unwrap(), b"one");
+            assert!(!Path::new(&first.info_path).exists());
+        }
+
+        #[test]
+        fn volume_trash_paths_are_relative_to_topdir() {
+            let trash = TrashDir {
+                root: PathBuf::from("/mnt/data/.Trash-1000"),
+                topdir: Some(PathBuf::from("/mnt/data")),
+            };
+            let recorded = trash.info_path_for(Path::new("/mnt/data/a b/c"));
+            assert_eq!(encode_path(&recorded), "a%20b/c");
+            assert_eq!(trash.original_from_info(&recorded), PathBuf::from("/mnt/data/a b/c"));
+
+            let trashes = || vec![volume_trash_at("/mnt/data/.Trash/1000", "/mnt/data")];
+            let of_info = trash_of_info(trashes(), Path::new("/mnt/data/.Trash/1000/info/x.trashinfo")).unwrap();
+            assert_eq!(of_info.topdir, Some(PathBuf::from("/mnt/data")));
+            assert!(trash_of_info(trashes(), Path::new("/tmp/.Trash/1000/info/x.trashinfo")).is_none());
+            assert!(trash_of_info(trashes(), Path::new("/mnt/data/.Trash/1000/info/../x.trashinfo")).is_none());
+        }
+
+        fn volume_trash_at(root: &str, topdir: &str) -> TrashDir {
+            TrashDir { root: PathBuf::from(root), topdir: Some(PathBuf::from(topdir)) }
+        }
+
+        #[test]
+        fn trashinfo_paths_cannot_escape() {
+            assert!(parse_trashinfo("[Trash Info]\nPath=/home/u/a%20b\nDeletionDate=x\n").is_some());
+            assert!(parse_trashinfo("[Trash Info]\nPath=/home/u/../../etc/passwd\n").is_none());
+            assert!(parse_trashinfo("[Trash Info]\nPath=a/%2E%2E/b\n").is_none());
+
+            // A relative path only makes sense for a per-volume trash.
+            let base = TempDir::new("trash-relative");
+            let trash = TrashDir { root: base.join("Trash"), topdir: None };
+            trash.ensure().unwrap();
+            fs::write(trash.files().join("x"), b"x").unwrap();
+            let info = trash.info().join("x.trashinfo");
+            fs::write(&info, "[Trash Info]\nPath=relative/x\nDeletionDate=x\n").unwrap();
+            assert!(restore_from(&trash, &info).is_err());
+        }
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+mod platform {
+    use super::TrashedItem;
+    use std::path::Path;
+
+    pub const SUPPORTED: bool = false;
+
+    const UNSUPPORTED: &str = "Moving to the trash is only supported on Linux; delete permanently instead";
+
+    pub fn trash(_path: &Path) -> Result<TrashedItem, String> {
+        Err(UNSUPPORTED.to_string())
+    }
+
+    pub fn list_trash() -> Result<Vec<TrashedItem>, String> {
+        Ok(Vec::new())
+    }
+
+    pub fn restore(_info_path: &Path) -> Result<TrashedItem, String> {
+        Err(UNSUPPORTED.to_string())
+    }
+}
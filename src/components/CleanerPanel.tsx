@@ -21,6 +21,7 @@ import {
     AccordionHeader,
     AccordionPanel,
     Badge,
+    Input,
 } from '@fluentui/react-components';
 import {
     DeleteRegular,
@@ -29,9 +30,12 @@ import {
     StethoscopeRegular,
     InfoRegular,
     FolderOpenRegular,
+    WrenchRegular,
+    DismissRegular,
+    AddRegular,
 } from '@fluentui/react-icons';
 import { invoke } from '@tauri-apps/api/core';
-import { JunkCategory, JunkItem } from '../types/cleaner';
+import { CleaningOptions, CleanupTool, JunkCategory, JunkItem, PruneResult, ToolCategory } from '../types/cleaner';
 
 const useStyles = makeStyles({
     container: {
@@ -82,6 +86,18 @@ const useStyles = makeStyles({
     },
 });
 
+// Folders searched for per-project build output, kept across sessions
+const PROJECT_ROOTS_KEY = 'cleanerProjectRoots';
+
+const loadProjectRoots = (): string[] => {
+    try {
+        const saved = JSON.parse(localStorage.getItem(PROJECT_ROOTS_KEY) || '[]');
+        return Array.isArray(saved) ? saved.filter((r): r is string => typeof r === 'string') : [];
+    } catch {
+        return [];
+    }
+};
+
 export const CleanerPanel = () => {
     const styles = useStyles();
     const [categories, setCategories] = useState<JunkCategory[]>([]);
@@ -92,13 +108,22 @@ export const CleanerPanel = () => {
     const [cleanDialogOpen, setCleanDialogOpen] = useState(false);
     const [errorDialogOpen, setErrorDialogOpen] = useState(false);
     const [cleaningErrors, setCleaningErrors] = useState<string[]>([]);
+    // Caches pruned by their own tools (apt, journald, docker, ...)
+    const [toolCategories, setToolCategories] = useState<ToolCategory[]>([]);
+    const [pruneTarget, setPruneTarget] = useState<ToolCategory | null>(null);
+    const [prunePreview, setPrunePreview] = useState<PruneResult | null>(null);
+    const [pruneResult, setPruneResult] = useState<PruneResult | null>(null);
+    const [pruning, setPruning] = useState(false);
+    const [pruneError, setPruneError] = useState<string | null>(null);
+    const [projectRoots, setProjectRoots] = useState<string[]>(loadProjectRoots);
+    const [newRoot, setNewRoot] = useState('');
 
     // Initial scan
     useEffect(() => {
         handleScan();
     }, []);
 
-    const handleScan = async () => {
+    const handleScan = async (roots: string[] = projectRoots) => {
         setLoading(true);
         setScanning(true);
         setCategories([]);
@@ -106,13 +131,22 @@ export const CleanerPanel = () => {
         try {
             // Fake delay for effect if too fast?
             const start = Date.now();
-            const result = await invoke<JunkCategory[]>('scan_junk');
+            const [result, tools] = await Promise.all([
+                invoke<JunkCategory[]>('scan_junk_with_options', {
+                    options: { min_age_days: null, dry_run: false, skip_errors: true, project_roots: roots },
+                }),
+                invoke<ToolCategory[]>('scan_tool_caches').catch((e) => {
+                    console.error(e);
+                    return [] as ToolCategory[];
+                }),
+            ]);
             const end = Date.now();
             if (end - start < 800) {
                 await new Promise(r => setTimeout(r, 800 - (end - start)));
             }
 
             setCategories(result);
+            setToolCategories(tools);
 
             // Auto-select all by default? Or let user choose? 
             // Let's auto-select "safe" ones. For now, select all.
@@ -155,6 +189,71 @@ export const CleanerPanel = () => {
         }
     };
 
+    const updateProjectRoots = (roots: string[]) => {
+        setProjectRoots(roots);
+        localStorage.setItem(PROJECT_ROOTS_KEY, JSON.stringify(roots));
+        handleScan(roots);
+    };
+
+    const handleAddRoot = () => {
+        const root = newRoot.trim();
+        setNewRoot('');
+        if (root && !projectRoots.includes(root)) {
+            updateProjectRoots([...projectRoots, root]);
+        }
+    };
+
+    const pruneOptions = (dryRun: boolean): CleaningOptions => ({
+        min_age_days: null,
+        dry_run: dryRun,
+        skip_errors: true,
+    });
+
+    const pruneTool = (tool: CleanupTool, dryRun: boolean) =>
+        invoke<PruneResult>('prune_tool_cache', { tool, options: pruneOptions(dryRun) });
+
+    // Opening the prune dialog always starts with a dry run, so the user sees
+    // the exact commands before anything runs.
+    const handleOpenPrune = async (category: ToolCategory) => {
+        setPruneTarget(category);
+        setPrunePreview(null);
+        setPruneResult(null);
+        setPruneError(null);
+        setPruning(true);
+        try {
+            setPrunePreview(await pruneTool(category.tool, true));
+        } catch (e) {
+            setPruneError(String(e));
+        } finally {
+            setPruning(false);
+        }
+    };
+
+    const handlePrune = async () => {
+        if (!pruneTarget) return;
+        setPruning(true);
+        setPruneError(null);
+        try {
+            setPruneResult(await pruneTool(pruneTarget.tool, false));
+            const tools = await invoke<ToolCategory[]>('scan_tool_caches');
+            setToolCategories(tools);
+        } catch (e) {
+            setPruneError(String(e));
+        } finally {
+            setPruning(false);
+        }
+    };
+
+    // What the prune dialog shows: the real run once it happened, else the dry run
+    const shownPrune = pruneResult ?? prunePreview;
+
+    const closePruneDialog = () => {
+        setPruneTarget(null);
+        setPrunePreview(null);
+        setPruneResult(null);
+        setPruneError(null);
+    };
+
     const formatSize = (bytes: number): string => {
         if (bytes === 0) return '0 B';
         const k = 1024;
@@ -195,11 +294,44 @@ export const CleanerPanel = () => {
                     <Subtitle2 style={{ fontSize: '20px' }}>System Cleaner</Subtitle2>
                     <Text block style={{ color: '#aaa' }}>Remove temporary files and free up space.</Text>
                 </div>
-                <Button appearance="subtle" icon={<ArrowClockwiseRegular />} onClick={handleScan} disabled={loading}>
+                <Button appearance="subtle" icon={<ArrowClockwiseRegular />} onClick={() => handleScan()} disabled={loading}>
                     Rescan
                 </Button>
             </div>
 
+            {/* Project folders searched for build output (target/, node_modules) */}
+            <div style={{ display: 'flex', flexDirection: 'column', gap: '6px' }}>
+                <Text size={200} style={{ color: '#aaa' }}>
+                    Project folders searched for build output (target/, node_modules):
+                </Text>
+                <div style={{ display: 'flex', flexWrap: 'wrap', alignItems: 'center', gap: '6px' }}>
+                    {projectRoots.map(root => (
+                        <Badge key={root} appearance="tint" size="large" style={{ gap: '4px' }}>
+                            {root}
+                            <Button
+                                appearance="transparent"
+                                size="small"
+                                icon={<DismissRegular />}
+                                title="Stop searching this folder"
+                                disabled={loading}
+                                onClick={() => updateProjectRoots(projectRoots.filter(r => r !== root))}
+                            />
+                        </Badge>
+                    ))}
+                    <Input
+                        size="small"
+                        placeholder="/home/me/projects"
+                        value={newRoot}
+                        onChange={(e, data) => setNewRoot(data.value)}
+                        onKeyDown={(e) => { if (e.key === 'Enter') handleAddRoot(); }}
+                        disabled={loading}
+                    />
+                    <Button size="small" icon={<AddRegular />} onClick={handleAddRoot} disabled={loading || !newRoot.trim()}>
+                        Add folder
+                    </Button>
+                </div>
+            </div>
+
             {/* Summary / Hero Section */}
             <div className={styles.summaryCard}>
                 {deleting ? (
@@ -246,7 +378,7 @@ export const CleanerPanel = () => {
             </div>
 
             {/* Details List */}
-            {categories.length > 0 && !scanning && !deleting && (
+            {(categories.length > 0 || toolCategories.length > 0) && !scanning && !deleting && (
                 <div className={styles.listContainer}>
                     <Accordion multiple collapsible>
                         {categories.map((cat) => {
@@ -313,9 +445,98 @@ export const CleanerPanel = () => {
                             );
                         })}
                     </Accordion>
+
+                    {toolCategories.length > 0 && (
+                        <div style={{ marginTop: '12px' }}>
+                            <Text weight="semibold" block style={{ padding: '8px 16px' }}>Cleaned by their own tools</Text>
+                            {toolCategories.map(tool => (
+                                <div key={tool.tool} className={styles.itemRow}>
+                                    <div style={{ display: 'flex', alignItems: 'center', gap: '10px', minWidth: 0 }}>
+                                        <WrenchRegular />
+                                        <div style={{ display: 'flex', flexDirection: 'column', minWidth: 0 }}>
+                                            <Text weight="semibold">{tool.name}</Text>
+                                            <Text size={200} style={{ color: '#aaa' }}>
+                                                {tool.description} • {tool.reclaimable != null ? formatSize(tool.reclaimable) : 'size unknown'}
+                                            </Text>
+                                        </div>
+                                        {tool.needs_root && (
+                                            <Badge appearance="outline" color="warning">Admin</Badge>
+                                        )}
+                                    </div>
+                                    <Button size="small" onClick={() => handleOpenPrune(tool)}>
+                                        Prune…
+                                    </Button>
+                                </div>
+                            ))}
+                        </div>
+                    )}
                 </div>
             )}
 
+            {/* Tool Prune Dialog: dry run first, then the real prune */}
+            <Dialog open={pruneTarget !== null} onOpenChange={(event, data) => { if (!data.open && !pruning) closePruneDialog(); }}>
+                <DialogSurface>
+                    <DialogBody>
+                        <DialogTitle>{pruneResult ? 'Prune finished' : 'Prune'} {pruneTarget?.name}</DialogTitle>
+                        <DialogContent>
+                            {pruning && <ProgressBar style={{ marginBottom: '12px' }} />}
+                            {pruneError && (
+                                <Text block style={{ color: 'var(--colorPaletteRedForeground1)', marginBottom: '12px' }}>
+                                    {pruneError}
+                                </Text>
+                            )}
+                            {shownPrune && (
+                                <>
+                                    <Text block style={{ marginBottom: '8px' }}>
+                                        {pruneResult
+                                            ? (shownPrune.success ? 'The tool reported success.' : 'The tool reported an error.')
+                                            : 'Dry run: nothing has been changed yet. These commands will run:'}
+                                    </Text>
+                                    <div style={{
+                                        maxHeight: '300px',
+                                        overflowY: 'auto',
+                                        backgroundColor: tokens.colorNeutralBackground2,
+                                        padding: '12px',
+                                        borderRadius: '4px',
+                                        border: `1px solid ${tokens.colorNeutralStroke1}`,
+                                        fontFamily: 'monospace',
+                                        fontSize: '12px',
+                                        whiteSpace: 'pre-wrap',
+                                    }}>
+                                        {shownPrune.commands.map(c => `$ ${c.join(' ')}`).join('\n')}
+                                        {shownPrune.output && `\n\n${shownPrune.output}`}
+                                    </div>
+                                    {pruneResult && pruneResult.reclaimable_after != null && (
+                                        <Text block style={{ marginTop: '8px', color: '#aaa' }}>
+                                            Still reclaimable: {formatSize(pruneResult.reclaimable_after)}
+                                        </Text>
+                                    )}
+                                    {!pruneResult && pruneTarget?.needs_root && (
+                                        <Text block style={{ marginTop: '8px', color: '#aaa' }}>
+                                            You will be asked for an administrator password.
+                                        </Text>
+                                    )}
+                                </>
+                            )}
+                        </DialogContent>
+                        <DialogActions>
+                            <Button appearance="secondary" onClick={closePruneDialog} disabled={pruning}>
+                                {pruneResult ? 'Close' : 'Cancel'}
+                            </Button>
+                            {!pruneResult && (
+                                <Button
+                                    appearance="primary"
+                                    onClick={handlePrune}
+                                    disabled={pruning || !prunePreview || prunePreview.commands.length === 0}
+                                >
+                                    Prune
+                                </Button>
+                            )}
+                        </DialogActions>
+                    </DialogBody>
+                </DialogSurface>
+            </Dialog>
+
             {/* Clean Confirmation Dialog */}
             <Dialog open={cleanDialogOpen} onOpenChange={(event, data) => setCleanDialogOpen(data.open)}>
                 <DialogSurface>
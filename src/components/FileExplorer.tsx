@@ -31,6 +31,13 @@ import {
     DialogTitle,
     DialogContent,
     DialogActions,
+    Checkbox,
+    Toast,
+    Toaster,
+    ToastTitle,
+    ToastBody,
+    useToastController,
+    useId,
 } from '@fluentui/react-components';
 import {
     FolderRegular,
@@ -56,7 +63,7 @@ import { BreadcrumbPath } from './BreadcrumbPath';
 import { CleanerPanel } from './CleanerPanel';
 import { invoke } from '@tauri-apps/api/core';
 import { listen } from '@tauri-apps/api/event';
-import { FileNode } from '@/types';
+import { FileNode, TrashedItem } from '@/types';
 import { FileMetadata } from '@/types/ai-types';
 import { onAgentAction } from '@/lib/agent/action-bus';
 
@@ -107,16 +114,21 @@ interface ExplorerState {
 }
 
 interface ScanProgressPayload {
+    scan_id: string;
     path: string;
     count: number;
     size: number;
     errors: number;
+    current_path: string;
+    files_per_sec: number;
+    bytes_per_sec: number;
+    expected_size: number | null;
+    eta_secs: number | null;
 }
 
-const ScanProgressBanner = ({ progress, onCancel, speed }: {
+const ScanProgressBanner = ({ progress, onCancel }: {
     progress: ScanProgressPayload;
     onCancel: () => void;
-    speed: number;
 }) => {
     const formatSize = (bytes: number): string => {
         if (bytes === 0) return '0 B';
@@ -126,6 +138,12 @@ const ScanProgressBanner = ({ progress, onCancel, speed }: {
         return parseFloat((bytes / Math.pow(k, i)).toFixed(2)) + ' ' + sizes[i];
     };
 
+    const formatEta = (secs: number): string => {
+        if (secs < 60) return `${secs}s`;
+        if (secs < 3600) return `${Math.floor(secs / 60)}m ${secs % 60}s`;
+        return `${Math.floor(secs / 3600)}h ${Math.floor((secs % 3600) / 60)}m`;
+    };
+
     // Determine if screen is wide (desktop) or compact
     const [isWideScreen, setIsWideScreen] = useState(window.innerWidth > 1024);
 
@@ -169,9 +187,9 @@ const ScanProgressBanner = ({ progress, onCancel, speed }: {
                             color: 'var(--colorNeutralForeground2)',
                             fontSize: '13px'
                         }}
-                        title={progress.path}
+                        title={progress.current_path || progress.path}
                     >
-                        {progress.path}
+                        {progress.current_path || progress.path}
                     </Text>
                 </div>
 
@@ -189,8 +207,16 @@ const ScanProgressBanner = ({ progress, onCancel, speed }: {
                             {formatSize(progress.size)}
                         </Text>
                         <Text style={{ color: 'var(--colorNeutralForeground2)' }}>
-                            {Math.round(speed)}/sec
+                            {Math.round(progress.files_per_sec).toLocaleString()} items/sec
+                        </Text>
+                        <Text style={{ color: 'var(--colorNeutralForeground2)' }}>
+                            {formatSize(Math.round(progress.bytes_per_sec))}/sec
                         </Text>
+                        {progress.eta_secs != null && (
+                            <Text style={{ color: 'var(--colorNeutralForeground2)' }}>
+                                ~{formatEta(progress.eta_secs)} left
+                            </Text>
+                        )}
                         {hasErrors && (
                             <Tooltip
                                 content={`${progress.errors.toLocaleString()} directories or files could not be accessed due to permission restrictions. The displayed size represents accessible files only.`}
@@ -258,16 +284,20 @@ const ScanProgressBanner = ({ progress, onCancel, speed }: {
                         color: 'var(--colorNeutralForeground2)',
                         marginBottom: '8px'
                     }}
-                    title={progress.path}
+                    title={progress.current_path || progress.path}
                 >
-                    {progress.path}
+                    {progress.current_path || progress.path}
                 </Text>
 
                 <ProgressBar style={{ marginBottom: '8px' }} />
 
                 <div style={{ display: 'flex', flexDirection: 'column', gap: '4px' }}>
                     <Caption1 style={{ color: 'var(--colorNeutralForeground2)' }}>
-                        {progress.count.toLocaleString()} items • {formatSize(progress.size)} • {Math.round(speed)}/sec
+                        {progress.count.toLocaleString()} items • {formatSize(progress.size)}
+                    </Caption1>
+                    <Caption1 style={{ color: 'var(--colorNeutralForeground2)' }}>
+                        {Math.round(progress.files_per_sec).toLocaleString()} items/sec • {formatSize(Math.round(progress.bytes_per_sec))}/sec
+                        {progress.eta_secs != null && ` • ~${formatEta(progress.eta_secs)} left`}
                     </Caption1>
                     {hasErrors && (
                         <Caption1 style={{ color: 'var(--colorPaletteYellowForeground1)', display: 'flex', alignItems: 'center', gap: '4px' }}>
@@ -304,9 +334,7 @@ export const FileExplorer = ({ onContextChange, onAskAgent }: FileExplorerProps)
     // Scan Progress State
     const [scanProgress, setScanProgress] = useState<ScanProgressPayload | null>(null);
     const [isScanning, setIsScanning] = useState(false);
-    const [scanSpeed, setScanSpeed] = useState(0);
-    const lastProgressRef = useRef<{ count: number, time: number } | null>(null);
-    const currentScanPathRef = useRef<string | null>(null); // Track which path is being scanned
+    const currentScanIdRef = useRef<string | null>(null); // Id of the running scan: filters its progress events, targets cancel_scan
     const scanCompletedRef = useRef<boolean>(false); // Flag to prevent race condition
 
     // Context synchronization
@@ -337,6 +365,15 @@ export const FileExplorer = ({ onContextChange, onAskAgent }: FileExplorerProps)
     const [propertiesDialogOpen, setPropertiesDialogOpen] = React.useState(false);
     const [dialogItem, setDialogItem] = React.useState<FileNode | null>(null);
     const [dialogItems, setDialogItems] = React.useState<FileNode[] | null>(null);
+    const [permanentDelete, setPermanentDelete] = React.useState(false);
+    // Where the trash isn't supported every delete is permanent.
+    const [trashSupported, setTrashSupported] = React.useState(true);
+    const toasterId = useId('explorer-toaster');
+    const { dispatchToast } = useToastController(toasterId);
+
+    React.useEffect(() => {
+        invoke<boolean>('trash_supported').then(setTrashSupported).catch(() => setTrashSupported(false));
+    }, []);
 
     // Compute all selected item objects (multi-select)
     const selectedItemsList = React.useMemo(() => {
@@ -394,24 +431,22 @@ export const FileExplorer = ({ onContextChange, onAskAgent }: FileExplorerProps)
     ];
 
     const fetchData = async (path: string, forceRefresh: boolean = false) => {
-        // Set the current scan path and reset completion flag
-        currentScanPathRef.current = path;
+        // Drop progress of any earlier scan until this one has an id, and reset completion flag
+        currentScanIdRef.current = null;
         scanCompletedRef.current = false;
 
         setState(prev => ({ ...prev, loading: true, error: null }));
         setIsScanning(true);
         setScanProgress(null);
-        setScanSpeed(0);
-        lastProgressRef.current = null;
 
         try {
             if (path === '') {
                 // Fetch Drives
                 const drives = await invoke<FileNode[]>('get_drives');
+                const used = drives.reduce((sum, d) => sum + d.size, 0);
 
                 // Mark scan as completed BEFORE clearing state
                 scanCompletedRef.current = true;
-                currentScanPathRef.current = null;
 
                 setState(prev => ({
                     ...prev,
@@ -420,8 +455,11 @@ export const FileExplorer = ({ onContextChange, onAskAgent }: FileExplorerProps)
                     data: {
                         name: 'This PC',
                         path: '',
-                        size: drives.reduce((sum, d) => sum + d.size, 0),
+                        size: used,
+                        apparent_size: used,
+                        allocated_size: used,
                         is_dir: true,
+                        kind: 'directory',
                         children: drives,
                         last_modified: 0,
                         file_count: drives.length
@@ -431,22 +469,20 @@ export const FileExplorer = ({ onContextChange, onAskAgent }: FileExplorerProps)
                 setSelectedItems(new Set());
                 setIsScanning(false);
                 setScanProgress(null);
-                setScanSpeed(0);
                 return;
             }
 
             const command = forceRefresh ? 'refresh_scan' : 'scan_dir';
-            const data = await invoke<FileNode>(command, { path });
+            const scanId = crypto.randomUUID();
+            currentScanIdRef.current = scanId;
+            const data = await invoke<FileNode>(command, { path, scanId });
 
             // Mark scan as completed BEFORE clearing state - this prevents race condition
             scanCompletedRef.current = true;
-            currentScanPathRef.current = null;
 
             // Immediately clear scanning state when data arrives
             setIsScanning(false);
             setScanProgress(null);
-            setScanSpeed(0);
-            lastProgressRef.current = null;
 
             // Then update the UI with the new data
             setState(prev => ({ ...prev, loading: false, data, path }));
@@ -454,14 +490,11 @@ export const FileExplorer = ({ onContextChange, onAskAgent }: FileExplorerProps)
         } catch (e: unknown) {
             // Mark as completed even on error
             scanCompletedRef.current = true;
-            currentScanPathRef.current = null;
 
             setState(prev => ({ ...prev, loading: false, error: String(e) }));
             // Clear scanning state on error too
             setIsScanning(false);
             setScanProgress(null);
-            setScanSpeed(0);
-            lastProgressRef.current = null;
         }
     };
 
@@ -477,33 +510,15 @@ export const FileExplorer = ({ onContextChange, onAskAgent }: FileExplorerProps)
         fetchData(initialPath);
 
         const unlistenPromise = listen<ScanProgressPayload>('scan-progress', (event) => {
-            // CRITICAL: Ignore progress events after scan is completed or for different paths
+            // CRITICAL: Ignore progress events after scan is completed or from other scans
             if (scanCompletedRef.current) {
                 return;
             }
 
-            if (currentScanPathRef.current !== event.payload.path) {
+            if (currentScanIdRef.current !== event.payload.scan_id) {
                 return;
             }
 
-            const now = Date.now();
-            const currentCount = event.payload.count;
-
-            if (lastProgressRef.current) {
-                const deltaCount = currentCount - lastProgressRef.current.count;
-                const deltaTime = (now - lastProgressRef.current.time) / 1000;
-                if (deltaTime > 0.3) { // Update speed every 300ms for smoother updates
-                    const newSpeed = deltaCount / deltaTime;
-                    // Only update speed if it's meaningful (avoid showing 0 during scanning)
-                    if (newSpeed > 0) {
-                        setScanSpeed(newSpeed);
-                    }
-                    lastProgressRef.current = { count: currentCount, time: now };
-                }
-            } else {
-                lastProgressRef.current = { count: currentCount, time: now };
-            }
-
             setScanProgress(event.payload);
             setIsScanning(true);
         });
@@ -575,20 +590,51 @@ export const FileExplorer = ({ onContextChange, onAskAgent }: FileExplorerProps)
         }
     };
 
+    const restoreTrashed = async (trashed: TrashedItem[]) => {
+        try {
+            for (const item of trashed) {
+                await invoke('restore_trashed_item', { infoPath: item.info_path });
+            }
+            fetchData(state.path, true);
+        } catch (e) {
+            console.error(`Failed to restore: ${e}`);
+            alert(`Failed to restore: ${e}`);
+        }
+    };
+
     const confirmDelete = async () => {
         const items = dialogItems;
         if (!items || items.length === 0) return;
+        const permanent = permanentDelete || !trashSupported;
+        const trashed: TrashedItem[] = [];
         try {
             for (const item of items) {
-                await invoke('delete_item', { path: item.path });
+                const result = await invoke<TrashedItem | null>('delete_item', { path: item.path, permanent });
+                if (result) trashed.push(result);
             }
             fetchData(state.path, true);
             setDeleteDialogOpen(false);
             setDialogItems(null);
+            setPermanentDelete(false);
         } catch (e) {
             console.error(`Failed to delete: ${e}`);
             alert(`Failed to delete: ${e}`);
         }
+        if (trashed.length > 0) {
+            dispatchToast(
+                <Toast>
+                    <ToastTitle action={
+                        <Button appearance="transparent" size="small" onClick={() => restoreTrashed(trashed)}>Undo</Button>
+                    }>
+                        Moved to Trash
+                    </ToastTitle>
+                    <ToastBody>
+                        {trashed.length === 1 ? trashed[0].original_path : `${trashed.length} items`}
+                    </ToastBody>
+                </Toast>,
+                { intent: 'success', timeout: 8000 }
+            );
+        }
     };
 
     const handleRevealInExplorer = async (item: FileNode) => {
@@ -620,19 +666,23 @@ export const FileExplorer = ({ onContextChange, onAskAgent }: FileExplorerProps)
     const handleCancelScan = async () => {
         // Mark as completed to stop accepting progress events
         scanCompletedRef.current = true;
-        currentScanPathRef.current = null;
 
-        await invoke('cancel_scan');
+        const scanId = currentScanIdRef.current;
+        currentScanIdRef.current = null;
+        if (scanId) {
+            await invoke('cancel_scan', { scanId });
+        }
         setIsScanning(false);
         setScanProgress(null);
-        setScanSpeed(0);
-        lastProgressRef.current = null;
     };
 
     const items = state.data?.children || [];
 
+    const deletesPermanently = permanentDelete || !trashSupported;
+
     return (
         <div className={styles.container}>
+            <Toaster toasterId={toasterId} />
             {/* Toolbar */}
             <div className={styles.toolbar}>
                 <Tooltip content="Home" relationship="label">
@@ -682,7 +732,6 @@ export const FileExplorer = ({ onContextChange, onAskAgent }: FileExplorerProps)
             {isScanning && scanProgress && (
                 <ScanProgressBanner
                     progress={scanProgress}
-                    speed={scanSpeed}
                     onCancel={handleCancelScan}
                 />
             )}
@@ -843,7 +892,10 @@ export const FileExplorer = ({ onContextChange, onAskAgent }: FileExplorerProps)
                             {/* Delete Confirmation Dialog */}
                             <Dialog open={deleteDialogOpen} onOpenChange={(event, data) => {
                                 setDeleteDialogOpen(data.open);
-                                if (!data.open) setDialogItems(null);
+                                if (!data.open) {
+                                    setDialogItems(null);
+                                    setPermanentDelete(false);
+                                }
                             }}>
                                 <DialogSurface>
                                     <DialogBody>
@@ -852,28 +904,35 @@ export const FileExplorer = ({ onContextChange, onAskAgent }: FileExplorerProps)
                                             {dialogItems && dialogItems.length === 1 ? (
                                                 <>
                                                     <Text>
-                                                        Are you sure you want to permanently delete <strong>{dialogItems[0].name}</strong>?
+                                                        {deletesPermanently ? 'Permanently delete' : 'Move'} <strong>{dialogItems[0].name}</strong>{deletesPermanently ? '' : ' to the Trash'}?
                                                     </Text>
                                                     {dialogItems[0].is_dir && (
                                                         <Text block style={{ marginTop: '10px', color: 'var(--colorPaletteRedForeground1)' }}>
-                                                            Warning: This is a folder. All contents will be deleted.
+                                                            This is a folder. All of its contents will be {deletesPermanently ? 'deleted' : 'moved'} too.
                                                         </Text>
                                                     )}
                                                 </>
                                             ) : (
                                                 <>
                                                     <Text>
-                                                        Are you sure you want to permanently delete <strong>{dialogItems?.length}</strong> items?
+                                                        {deletesPermanently ? 'Permanently delete' : 'Move'} <strong>{dialogItems?.length}</strong> items{deletesPermanently ? '' : ' to the Trash'}?
                                                     </Text>
                                                     <Text block style={{ marginTop: '10px', color: 'var(--colorPaletteRedForeground1)' }}>
-                                                        Warning: This action cannot be undone.
+                                                        {deletesPermanently ? 'This cannot be undone.' : 'Items can be restored from the Trash.'}
                                                     </Text>
                                                 </>
                                             )}
+                                            <Checkbox
+                                                style={{ marginTop: '10px', display: 'block' }}
+                                                label={trashSupported ? 'Delete permanently (cannot be undone)' : 'The Trash is not available on this system; items will be deleted permanently'}
+                                                checked={deletesPermanently}
+                                                disabled={!trashSupported}
+                                                onChange={(_, data) => setPermanentDelete(!!data.checked)}
+                                            />
                                         </DialogContent>
                                         <DialogActions>
                                             <Button appearance="secondary" onClick={() => { setDeleteDialogOpen(false); setDialogItems(null); }}>Cancel</Button>
-                                            <Button appearance="primary" style={{ backgroundColor: '#d13438', color: 'white' }} onClick={confirmDelete}>Delete</Button>
+                                            <Button appearance="primary" style={{ backgroundColor: '#d13438', color: 'white' }} onClick={confirmDelete}>{deletesPermanently ? 'Delete Permanently' : 'Move to Trash'}</Button>
                                         </DialogActions>
                                     </DialogBody>
                                 </DialogSurface>
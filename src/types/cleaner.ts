@@ -3,6 +3,13 @@ export interface JunkItem {
     name: string;
     size: number;
     description: string;
+    age_days?: number | null;
+    /** Owning project directory, for build output in developer caches. */
+    project?: string;
+    /** Last build or install, seconds since the epoch. */
+    last_build?: number;
+    /** System logs: rotated ones are deleted, active ones truncated in place. */
+    log_kind?: 'rotated' | 'active';
 }
 
 export interface JunkCategory {
@@ -13,3 +20,37 @@ export interface JunkCategory {
     total_size: number;
     icon: string;
 }
+
+export interface CleaningOptions {
+    min_age_days: number | null;
+    dry_run: boolean;
+    skip_errors: boolean;
+    /** Folders searched for per-project build output (`target/`, `node_modules`). */
+    project_roots?: string[];
+    journal_max_age_days?: number | null;
+    journal_max_size?: number | null;
+}
+
+export type CleanupTool = 'apt' | 'dnf' | 'journald' | 'docker' | 'podman' | 'flatpak' | 'snap';
+
+/** A cache pruned by its owning tool rather than by deleting files. */
+export interface ToolCategory {
+    tool: CleanupTool;
+    name: string;
+    description: string;
+    /** Null when the tool can't tell beforehand. */
+    reclaimable: number | null;
+    details: string[];
+    commands: string[][];
+    needs_root: boolean;
+}
+
+export interface PruneResult {
+    tool: CleanupTool;
+    dry_run: boolean;
+    commands: string[][];
+    success: boolean;
+    output: string;
+    reclaimable_before: number | null;
+    reclaimable_after: number | null;
+}
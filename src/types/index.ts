@@ -1,9 +1,104 @@
+export type NodeKind = 'file' | 'directory' | 'symlink';
+
+export type SizeMode = 'apparent' | 'allocated';
+
 export interface FileNode {
     name: string;
     path: string;
+    /** Apparent or allocated size, per `size_mode`. */
     size: number;
+    apparent_size: number;
+    allocated_size: number;
     is_dir: boolean;
+    kind: NodeKind;
+    link_target?: string;
     children?: FileNode[];
     last_modified: number;
     file_count: number;
+    inode?: number;
+    file_types?: FileTypeBreakdown;
+    uid?: number;
+    gid?: number;
+    mode?: number;
+    owners?: OwnerBreakdown;
+    size_mode?: SizeMode;
+    scan_errors?: ScanErrorLog;
+    drive?: DriveInfo;
+    /** Opaque fingerprint used by incremental rescans; do not interpret. */
+    tree_stamp?: number;
+}
+
+export interface TypeStat {
+    bytes: number;
+    count: number;
+}
+
+export interface FileTypeBreakdown {
+    by_extension: Record<string, TypeStat>;
+    by_category: Record<string, TypeStat>;
+}
+
+export interface OwnerBreakdown {
+    /** Keyed by uid. */
+    by_uid: Record<string, TypeStat>;
+}
+
+export interface DriveInfo {
+    mount_point: string;
+    label: string;
+    device: string;
+    fs_type: string;
+    total: number;
+    free: number;
+    used: number;
+    read_only: boolean;
+    removable: boolean;
+}
+
+export interface ScanError {
+    path: string;
+    kind: 'permission_denied' | 'not_found' | 'loop' | 'other';
+    message: string;
+}
+
+export interface ScanErrorLog {
+    errors: ScanError[];
+    total: number;
+}
+
+export interface TrashedItem {
+    /** Identifies the item for `restore_trashed_item`. */
+    info_path: string;
+    original_path: string;
+    trashed_path: string;
+    deleted_at: string;
+    is_dir: boolean;
+}
+
+export type ArchiveFormat = 'tar.gz' | 'zip';
+
+export interface FileOpProgress {
+    op_id: string;
+    phase: 'measuring' | 'copying' | 'archiving' | 'verifying' | 'removing';
+    current_path: string;
+    bytes_done: number;
+    bytes_total: number;
+    files_done: number;
+    files_total: number;
+}
+
+export interface FileOpItem {
+    source: string;
+    destination: string | null;
+    removed: boolean;
+    error: string | null;
+}
+
+export interface FileOpResult {
+    op_id: string;
+    destination: string;
+    items: FileOpItem[];
+    files: number;
+    bytes: number;
+    archive_size: number | null;
 }